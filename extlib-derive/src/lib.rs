@@ -0,0 +1,349 @@
+//! Derive macro to define typed errors interoperating with `extlib::error`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    Attribute, Data, DeriveInput, Expr, Fields, Ident, LitInt, LitStr, Token, parse_macro_input,
+};
+
+//-------------------------------------------------------------------------
+// Derive macro
+//-------------------------------------------------------------------------
+
+/// Derive `Display`, `std::error::Error` and, if error codes are given,
+/// `extlib::error::ErrorCode` for an enum or a struct.
+///
+/// Each variant of an enum, or the struct itself, is annotated by an
+/// `#[error(...)]` attribute containing a format string, which can refer to
+/// named fields like `{path}` and to tuple fields like `{0}`, followed by
+/// optional format arguments and the options below. Explicit positional
+/// arguments of tuple variants are numbered after their fields, like `{2}`
+/// for the first argument of a variant with two fields.
+///
+/// - `code = "E0001"`: error code of the variant or the struct.
+/// - `category = NotFound`: variant of `extlib::error::ErrorCategory`.
+/// - `exit_status = 2`: exit status overriding the one of the category.
+///
+/// The `category` and `exit_status` options can also be given to an enum in
+/// an `#[error(...)]` attribute without format string, as defaults of its
+/// variants. A field named `source`, or annotated by `#[source]`, is returned
+/// as the source of the error. It can be a typed error or a boxed error, like
+/// `Box<dyn Error + Send + Sync>`.
+///
+/// ```ignore
+/// use extlib::error::ExtError;
+///
+/// #[derive(Debug, ExtError)]
+/// #[error(category = InvalidData)]
+/// enum ConfigError {
+///     #[error("file not found: {path}", code = "C001", category = NotFound)]
+///     NotFound { path: String },
+///     #[error("invalid value of {0}", code = "C002")]
+///     Invalid(String),
+/// }
+/// ```
+#[proc_macro_derive(ExtError, attributes(error, source))]
+pub fn derive_ext_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+//-------------------------------------------------------------------------
+// Attributes
+//-------------------------------------------------------------------------
+
+/// Content of an `#[error(...)]` attribute.
+#[derive(Default)]
+struct ErrorAttr {
+    format: Option<LitStr>,
+    args: Vec<Expr>,
+    code: Option<LitStr>,
+    category: Option<Ident>,
+    exit_status: Option<LitInt>,
+}
+
+impl Parse for ErrorAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attr = ErrorAttr::default();
+        if input.peek(LitStr) {
+            attr.format = Some(input.parse()?);
+        } else if !input.is_empty() {
+            attr.parse_option(input)?;
+        }
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+                attr.parse_option(input)?;
+            } else if attr.format.is_some() {
+                attr.args.push(input.parse()?);
+            } else {
+                return Err(input.error("format arguments require a format string"));
+            }
+        }
+        Ok(attr)
+    }
+}
+
+impl ErrorAttr {
+    fn parse_option(&mut self, input: ParseStream) -> syn::Result<()> {
+        let key: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        match key.to_string().as_str() {
+            "code" => self.code = Some(input.parse()?),
+            "category" => self.category = Some(input.parse()?),
+            "exit_status" => self.exit_status = Some(input.parse()?),
+            _ => {
+                let msg = "unknown option, expected `code`, `category` or `exit_status`";
+                return Err(syn::Error::new(key.span(), msg));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the `#[error(...)]` attribute among a list of attributes.
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Option<Self>> {
+        let mut result = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("error")) {
+            if result.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "duplicate #[error] attribute",
+                ));
+            }
+            result = Some(attr.parse_args()?);
+        }
+        Ok(result)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Code generation
+//-------------------------------------------------------------------------
+
+/// Generated code of a variant or a struct.
+struct Arm {
+    pattern: TokenStream2,
+    display: TokenStream2,
+    source: TokenStream2,
+    code: Option<TokenStream2>,
+    category: TokenStream2,
+    exit_status: TokenStream2,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let type_attr = ErrorAttr::from_attrs(&input.attrs)?.unwrap_or_default();
+
+    let arms = match &input.data {
+        Data::Struct(data) => {
+            let pattern = quote!(#name);
+            vec![expand_arm(
+                pattern,
+                &data.fields,
+                type_attr,
+                &ErrorAttr::default(),
+                name,
+            )?]
+        }
+        Data::Enum(data) => {
+            if type_attr.format.is_some() {
+                let msg = "the #[error] attribute of an enum cannot contain a format string";
+                return Err(syn::Error::new_spanned(name, msg));
+            }
+            let mut arms = vec![];
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let attr = ErrorAttr::from_attrs(&variant.attrs)?.unwrap_or_default();
+                let pattern = quote!(#name::#ident);
+                arms.push(expand_arm(
+                    pattern,
+                    &variant.fields,
+                    attr,
+                    &type_attr,
+                    ident,
+                )?);
+            }
+            arms
+        }
+        Data::Union(_) => {
+            let msg = "ExtError cannot be derived for unions";
+            return Err(syn::Error::new_spanned(name, msg));
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let patterns: Vec<_> = arms.iter().map(|arm| &arm.pattern).collect();
+    let displays = arms.iter().map(|arm| &arm.display);
+    let sources = arms.iter().map(|arm| &arm.source);
+
+    let mut tokens = quote! {
+        impl #impl_generics ::core::fmt::Display for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#patterns => #displays,)*
+                }
+            }
+        }
+
+        impl #impl_generics ::std::error::Error for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn source(&self) -> ::core::option::Option<&(dyn ::std::error::Error + 'static)> {
+                match self {
+                    #(#patterns => #sources,)*
+                }
+            }
+        }
+    };
+
+    if arms.iter().any(|arm| arm.code.is_some()) {
+        let mut codes = vec![];
+        for arm in &arms {
+            match &arm.code {
+                Some(code) => codes.push(code),
+                None => {
+                    let msg = "missing error code, as other variants have error codes";
+                    return Err(syn::Error::new_spanned(&arm.pattern, msg));
+                }
+            }
+        }
+        let categories = arms.iter().map(|arm| &arm.category);
+        let exit_statuses = arms.iter().map(|arm| &arm.exit_status);
+        tokens.extend(quote! {
+            impl #impl_generics ::extlib::error::ErrorCode for #name #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn code(&self) -> &str {
+                    match self {
+                        #(#patterns => #codes,)*
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn category(&self) -> ::extlib::error::ErrorCategory {
+                    match self {
+                        #(#patterns => #categories,)*
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn exit_status(&self) -> i32 {
+                    match self {
+                        #(#patterns => #exit_statuses,)*
+                    }
+                }
+            }
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Generate the code of a variant or a struct.
+fn expand_arm(
+    path: TokenStream2,
+    fields: &Fields,
+    attr: ErrorAttr,
+    type_attr: &ErrorAttr,
+    ident: &Ident,
+) -> syn::Result<Arm> {
+    let bindings: Vec<Ident> = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| match &field.ident {
+            Some(name) => name.clone(),
+            None => format_ident!("_{}", idx),
+        })
+        .collect();
+    let pattern = match fields {
+        Fields::Named(_) => quote!(#path { #(#bindings),* }),
+        Fields::Unnamed(_) => quote!(#path ( #(#bindings),* )),
+        Fields::Unit => quote!(#path),
+    };
+
+    let Some(format) = &attr.format else {
+        let msg = format!("missing #[error(\"...\")] attribute of `{ident}`");
+        return Err(syn::Error::new_spanned(ident, msg));
+    };
+    let format = match fields {
+        Fields::Unnamed(_) => {
+            let format_value = rewrite_tuple_fields(&format.value(), fields.len());
+            LitStr::new(&format_value, format.span())
+        }
+        _ => format.clone(),
+    };
+    let args = &attr.args;
+    let display = quote!(::core::write!(__formatter, #format #(, #args)*));
+
+    let mut source = quote!(::core::option::Option::None);
+    for (field, binding) in fields.iter().zip(&bindings) {
+        let is_source = field
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("source"))
+            || matches!(&field.ident, Some(name) if name == "source");
+        if is_source {
+            source = quote!({
+                use ::extlib::error::private::AsDynError as _;
+                ::core::option::Option::Some(#binding.as_dyn_error())
+            });
+        }
+    }
+
+    let code = attr.code.as_ref().map(|code| quote!(#code));
+    let category = match attr.category.as_ref().or(type_attr.category.as_ref()) {
+        Some(category) => quote!(::extlib::error::ErrorCategory::#category),
+        None => quote!(::extlib::error::ErrorCategory::Internal),
+    };
+    let exit_status = match attr.exit_status.as_ref().or(type_attr.exit_status.as_ref()) {
+        Some(status) => quote!(#status),
+        None => quote!(#category.exit_status()),
+    };
+
+    Ok(Arm {
+        pattern,
+        display,
+        source,
+        code,
+        category,
+        exit_status,
+    })
+}
+
+/// Rewrite references to tuple fields in a format string, like `{0}`, to the
+/// names of their bindings, like `{_0}`. Indexes beyond the number of fields
+/// refer to the explicit format arguments following the fields, so that
+/// `{2}` of a struct with two fields is the first argument.
+fn rewrite_tuple_fields(format: &str, field_count: usize) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(idx) = rest.find('{') {
+        result.push_str(&rest[..=idx]);
+        rest = &rest[idx + 1..];
+        if let Some(after) = rest.strip_prefix('{') {
+            result.push('{');
+            rest = after;
+            continue;
+        }
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        match rest[..digits].parse::<usize>() {
+            Ok(index) if index < field_count => result.push('_'),
+            Ok(index) => {
+                result.push_str(&(index - field_count).to_string());
+                rest = &rest[digits..];
+            }
+            Err(_) => {}
+        }
+    }
+    result.push_str(rest);
+    result
+}
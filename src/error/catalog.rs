@@ -0,0 +1,460 @@
+//! Localization of error messages by message catalogs.
+
+use super::{Result, ResultExt};
+use core::fmt::{self, Display};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Localizer installed by `Localizer::install`.
+static LOCALIZER: RwLock<Option<Localizer>> = RwLock::new(None);
+
+//-------------------------------------------------------------------------
+// Catalog
+//-------------------------------------------------------------------------
+
+/// Catalog of the messages of a locale, identified by keys.
+///
+/// Catalogs are parsed from a Fluent-like `key = value` syntax:
+///
+/// ```text
+/// # Comments start with `#`.
+/// file-not-found = File not found: { $path }
+/// invalid-port = Invalid port { $port },
+///     expected a number between 1 and 65535.
+/// ```
+///
+/// Indented lines continue the message of the previous line. Placeholders are
+/// written `{ $name }` or `{name}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Create an empty catalog of a locale.
+    pub fn new(locale: impl Into<String>) -> Self {
+        Catalog {
+            locale: normalize_locale(&locale.into()),
+            messages: HashMap::new(),
+        }
+    }
+
+    /// Parse a catalog of a locale from its text.
+    pub fn parse(locale: impl Into<String>, text: &str) -> Result<Self> {
+        let mut catalog = Catalog::new(locale);
+        let mut last_key: Option<String> = None;
+        for (idx, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                match &last_key {
+                    Some(key) => {
+                        let message = catalog.messages.entry(key.clone()).or_default();
+                        if !message.is_empty() {
+                            message.push(' ');
+                        }
+                        message.push_str(trimmed);
+                        continue;
+                    }
+                    None => {
+                        crate::fail!("Line {}: unexpected indented line", idx + 1);
+                    }
+                }
+            }
+            let Some((key, message)) = trimmed.split_once('=') else {
+                crate::fail!("Line {}: expected `key = message`", idx + 1);
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                crate::fail!("Line {}: empty message key", idx + 1);
+            }
+            catalog.insert(key, message.trim());
+            last_key = Some(key.to_string());
+        }
+        Ok(catalog)
+    }
+
+    /// Load a catalog of a locale from a file.
+    pub fn load(locale: impl Into<String>, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read catalog {}", path.display()))?;
+        Catalog::parse(locale, &text).with_context(|| format!("Invalid catalog {}", path.display()))
+    }
+
+    /// Add or replace a message.
+    pub fn insert(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.messages.insert(key.into(), message.into());
+    }
+
+    /// Locale of the catalog.
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Message of a key, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Localizer
+//-------------------------------------------------------------------------
+
+/// Set of catalogs resolving messages in a locale, or else in a fallback
+/// locale.
+///
+/// ```no_run
+/// use extlib::error::{Catalog, Localizer, system_locale};
+///
+/// Localizer::new("en")
+///     .with_catalog(Catalog::load("en", "locales/en.ftl").unwrap())
+///     .with_catalog(Catalog::load("fr", "locales/fr.ftl").unwrap())
+///     .locale(system_locale().unwrap_or_default())
+///     .install();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Localizer {
+    catalogs: HashMap<String, Catalog>,
+    locale: String,
+    fallback: String,
+}
+
+impl Localizer {
+    /// Create a localizer with a fallback locale, which is also the locale in
+    /// use until changed by `Localizer::locale`.
+    pub fn new(fallback: impl AsRef<str>) -> Self {
+        let fallback = normalize_locale(fallback.as_ref());
+        Localizer {
+            catalogs: HashMap::new(),
+            locale: fallback.clone(),
+            fallback,
+        }
+    }
+
+    /// Add a catalog, merged with the catalog of the same locale, if any.
+    pub fn with_catalog(mut self, catalog: Catalog) -> Self {
+        match self.catalogs.get_mut(&catalog.locale) {
+            Some(existing) => existing.messages.extend(catalog.messages),
+            None => {
+                self.catalogs.insert(catalog.locale.clone(), catalog);
+            }
+        }
+        self
+    }
+
+    /// Set the locale in use. An empty locale selects the fallback locale.
+    pub fn locale(mut self, locale: impl AsRef<str>) -> Self {
+        let locale = normalize_locale(locale.as_ref());
+        self.locale = if locale.is_empty() {
+            self.fallback.clone()
+        } else {
+            locale
+        };
+        self
+    }
+
+    /// Locale in use.
+    pub fn current_locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Fallback locale.
+    pub fn fallback_locale(&self) -> &str {
+        &self.fallback
+    }
+
+    /// Find the message of a key in the locale in use, then in its language
+    /// without region, such as `fr` for `fr-CA`, then in the fallback locale.
+    pub fn message(&self, key: &str) -> Option<&str> {
+        let language = self.locale.split('-').next().unwrap_or_default();
+        [self.locale.as_str(), language, self.fallback.as_str()]
+            .into_iter()
+            .find_map(|locale| self.catalogs.get(locale)?.get(key))
+    }
+
+    /// Resolve the message of a key with named arguments.
+    ///
+    /// Keys without message are rendered with their arguments, such as
+    /// `file-not-found (path = a.txt)`.
+    pub fn resolve(&self, key: &str, args: &[(String, String)]) -> String {
+        match self.message(key) {
+            Some(message) => interpolate(message, args),
+            None => unresolved(key, args),
+        }
+    }
+
+    /// Install the localizer used to render localized messages, replacing the
+    /// one installed previously.
+    pub fn install(self) {
+        let mut localizer = LOCALIZER.write().unwrap_or_else(|err| err.into_inner());
+        *localizer = Some(self);
+    }
+}
+
+//-------------------------------------------------------------------------
+// Localized message
+//-------------------------------------------------------------------------
+
+/// Error identified by a message key and named arguments, rendered by the
+/// installed `Localizer`.
+///
+/// The key and the arguments are kept in the report, so that logs can record
+/// them regardless of the locale. They are usually created by `localized!`.
+///
+/// ```
+/// use extlib::error::Result;
+/// use extlib::{fail, localized};
+///
+/// fn open(path: &str) -> Result<()> {
+///     fail!(localized!("file-not-found", path = path))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedMessage {
+    key: String,
+    args: Vec<(String, String)>,
+}
+
+impl LocalizedMessage {
+    /// Create a message of a key without arguments.
+    pub fn new(key: impl Into<String>) -> Self {
+        LocalizedMessage {
+            key: key.into(),
+            args: vec![],
+        }
+    }
+
+    /// Add a named argument.
+    pub fn with_arg(mut self, name: impl Into<String>, value: impl Display) -> Self {
+        self.args.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Key of the message.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Named arguments of the message.
+    pub fn args(&self) -> &[(String, String)] {
+        &self.args
+    }
+
+    /// Render the message with a given localizer.
+    pub fn localize(&self, localizer: &Localizer) -> String {
+        localizer.resolve(&self.key, &self.args)
+    }
+}
+
+impl Display for LocalizedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let localizer = LOCALIZER.read().unwrap_or_else(|err| err.into_inner());
+        match localizer.as_ref() {
+            Some(localizer) => write!(f, "{}", self.localize(localizer)),
+            None => write!(f, "{}", unresolved(&self.key, &self.args)),
+        }
+    }
+}
+
+impl std::error::Error for LocalizedMessage {}
+
+/// Create a `LocalizedMessage` from a key and named arguments.
+///
+/// ```
+/// use extlib::localized;
+///
+/// let msg = localized!("invalid-port", port = 70000, max = 65535);
+/// assert_eq!(msg.key(), "invalid-port");
+/// ```
+#[macro_export]
+macro_rules! localized {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::error::LocalizedMessage::new($key)
+            $(.with_arg(stringify!($name), &$value))*
+    };
+}
+
+//-------------------------------------------------------------------------
+// Utilities
+//-------------------------------------------------------------------------
+
+/// Locale of the system, read from the `LC_ALL`, `LC_MESSAGES` or `LANG`
+/// environment variables, such as `fr-FR` for `fr_FR.UTF-8`.
+pub fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|value| normalize_locale(&value))
+        .find(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+}
+
+/// Normalize a locale like `fr_FR.UTF-8` to `fr-FR`.
+fn normalize_locale(locale: &str) -> String {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    locale.trim().replace('_', "-")
+}
+
+/// Replace the placeholders `{ $name }` and `{name}` of a message by the
+/// values of named arguments. Unknown placeholders are kept verbatim.
+fn interpolate(message: &str, args: &[(String, String)]) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let placeholder = &rest[start..start + len + 1];
+        let name = placeholder[1..len].trim().trim_start_matches('$');
+        match args.iter().find(|(arg, _)| arg == name) {
+            Some((_, value)) => result.push_str(value),
+            None => result.push_str(placeholder),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Render a key without message with its arguments.
+fn unresolved(key: &str, args: &[(String, String)]) -> String {
+    if args.is_empty() {
+        return key.to_string();
+    }
+    let args: Vec<String> = args
+        .iter()
+        .map(|(name, value)| format!("{name} = {value}"))
+        .collect();
+    format!("{key} ({})", args.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_messages_comments_and_continuation_lines() {
+        let text = "# Comment\n\
+                    file-not-found = File not found: { $path }\n\
+                    \n\
+                    invalid-port = Invalid port { $port },\n    \
+                    expected a number\n\t\
+                    between 1 and 65535.\n\
+                    empty =\n  \
+                    continued\n";
+        let catalog = Catalog::parse("en_US.UTF-8", text).unwrap();
+        assert_eq!(catalog.locale(), "en-US");
+        assert_eq!(
+            catalog.get("file-not-found"),
+            Some("File not found: { $path }")
+        );
+        assert_eq!(
+            catalog.get("invalid-port"),
+            Some("Invalid port { $port }, expected a number between 1 and 65535.")
+        );
+        assert_eq!(catalog.get("empty"), Some("continued"));
+        assert_eq!(catalog.get("# Comment"), None);
+    }
+
+    #[test]
+    fn parse_keeps_equal_signs_of_messages() {
+        let catalog = Catalog::parse("en", "formula = a = b + c").unwrap();
+        assert_eq!(catalog.get("formula"), Some("a = b + c"));
+    }
+
+    #[test]
+    fn parse_reports_the_line_of_errors() {
+        let err = Catalog::parse("en", "  indented first").unwrap_err();
+        assert_eq!(err.to_string(), "Line 1: unexpected indented line");
+        let err = Catalog::parse("en", "# Comment\nno separator").unwrap_err();
+        assert_eq!(err.to_string(), "Line 2: expected `key = message`");
+        let err = Catalog::parse("en", "a = b\n\n= message").unwrap_err();
+        assert_eq!(err.to_string(), "Line 3: empty message key");
+    }
+
+    #[test]
+    fn interpolate_both_placeholder_syntaxes() {
+        let args = args(&[("path", "a.txt"), ("port", "80")]);
+        assert_eq!(interpolate("File { $path }", &args), "File a.txt");
+        assert_eq!(interpolate("File {path}:{port}", &args), "File a.txt:80");
+        assert_eq!(interpolate("{$port}{ port }", &args), "8080");
+    }
+
+    #[test]
+    fn interpolate_keeps_unknown_placeholders() {
+        let args = args(&[("path", "a.txt")]);
+        assert_eq!(
+            interpolate("File { $name } at {path}", &args),
+            "File { $name } at a.txt"
+        );
+        assert_eq!(interpolate("Empty {}", &args), "Empty {}");
+    }
+
+    #[test]
+    fn interpolate_keeps_unclosed_placeholders() {
+        let args = args(&[("path", "a.txt")]);
+        assert_eq!(
+            interpolate("File {path} { $path", &args),
+            "File a.txt { $path"
+        );
+        assert_eq!(interpolate("{", &args), "{");
+    }
+
+    #[test]
+    fn normalize_locales() {
+        assert_eq!(normalize_locale("fr_FR.UTF-8"), "fr-FR");
+        assert_eq!(normalize_locale("de_DE@euro"), "de-DE");
+        assert_eq!(normalize_locale(" en "), "en");
+        assert_eq!(normalize_locale(""), "");
+    }
+
+    #[test]
+    fn resolve_region_then_language_then_fallback() {
+        let localizer = Localizer::new("en")
+            .with_catalog(Catalog::parse("en", "a = en a\nb = en b\nc = en c").unwrap())
+            .with_catalog(Catalog::parse("fr", "a = fr a\nb = fr b").unwrap())
+            .with_catalog(Catalog::parse("fr_CA", "a = ca a").unwrap())
+            .locale("fr_CA.UTF-8");
+        assert_eq!(localizer.current_locale(), "fr-CA");
+        assert_eq!(localizer.message("a"), Some("ca a"));
+        assert_eq!(localizer.message("b"), Some("fr b"));
+        assert_eq!(localizer.message("c"), Some("en c"));
+        assert_eq!(localizer.message("d"), None);
+    }
+
+    #[test]
+    fn resolve_unknown_keys_with_their_arguments() {
+        let localizer = Localizer::new("en").locale("");
+        assert_eq!(localizer.current_locale(), "en");
+        let args = args(&[("path", "a.txt"), ("line", "3")]);
+        assert_eq!(
+            localizer.resolve("file-not-found", &args),
+            "file-not-found (path = a.txt, line = 3)"
+        );
+        assert_eq!(localizer.resolve("file-not-found", &[]), "file-not-found");
+    }
+
+    #[test]
+    fn merge_catalogs_of_the_same_locale() {
+        let localizer = Localizer::new("en")
+            .with_catalog(Catalog::parse("en", "a = first\nb = kept").unwrap())
+            .with_catalog(Catalog::parse("en-US.UTF-8", "a = other").unwrap())
+            .with_catalog(Catalog::parse("en", "a = second").unwrap());
+        assert_eq!(localizer.message("a"), Some("second"));
+        assert_eq!(localizer.message("b"), Some("kept"));
+    }
+}
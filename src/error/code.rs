@@ -0,0 +1,159 @@
+//! Typed error codes and categories attached to reports.
+
+use alloc::string::{String, ToString};
+use core::fmt::{self, Debug, Display};
+
+//-------------------------------------------------------------------------
+// Error category
+//-------------------------------------------------------------------------
+
+/// Broad category of errors, used to derive exit codes and HTTP statuses.
+///
+/// Exit statuses follow the conventions of `sysexits.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorCategory {
+    /// Command line usage error.
+    Usage,
+    /// Input data is incorrect.
+    InvalidData,
+    /// Requested resource does not exist.
+    NotFound,
+    /// Insufficient permission to perform an operation.
+    PermissionDenied,
+    /// Service or resource is temporarily unavailable.
+    Unavailable,
+    /// Input/output error.
+    Io,
+    /// Configuration error.
+    Config,
+    /// Internal software error.
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Default exit status of the category.
+    pub fn exit_status(&self) -> i32 {
+        match self {
+            ErrorCategory::Usage => 64,
+            ErrorCategory::InvalidData => 65,
+            ErrorCategory::NotFound => 66,
+            ErrorCategory::PermissionDenied => 77,
+            ErrorCategory::Unavailable => 69,
+            ErrorCategory::Io => 74,
+            ErrorCategory::Config => 78,
+            ErrorCategory::Internal => 70,
+        }
+    }
+
+    /// Default HTTP status of the category.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCategory::Usage => 400,
+            ErrorCategory::InvalidData => 422,
+            ErrorCategory::NotFound => 404,
+            ErrorCategory::PermissionDenied => 403,
+            ErrorCategory::Unavailable => 503,
+            ErrorCategory::Io => 500,
+            ErrorCategory::Config => 500,
+            ErrorCategory::Internal => 500,
+        }
+    }
+}
+
+impl Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorCategory::Usage => "usage",
+            ErrorCategory::InvalidData => "invalid data",
+            ErrorCategory::NotFound => "not found",
+            ErrorCategory::PermissionDenied => "permission denied",
+            ErrorCategory::Unavailable => "unavailable",
+            ErrorCategory::Io => "io",
+            ErrorCategory::Config => "config",
+            ErrorCategory::Internal => "internal",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//-------------------------------------------------------------------------
+// Error code
+//-------------------------------------------------------------------------
+
+/// Trait of user-defined error codes, which can be attached to reports by
+/// `fail!(code = c, msg)` or `ReportExt::with_code`.
+///
+/// The `Display` of an error code is used as the error message when no
+/// message is given to `fail!`.
+pub trait ErrorCode: Debug + Display + Send + Sync + 'static {
+    /// Unique identifier of the error code, such as `E0042`.
+    fn code(&self) -> &str;
+
+    /// Category of the error code.
+    fn category(&self) -> ErrorCategory;
+
+    /// Exit status of a process terminated by this error.
+    fn exit_status(&self) -> i32 {
+        self.category().exit_status()
+    }
+
+    /// HTTP status of a response reporting this error.
+    fn http_status(&self) -> u16 {
+        self.category().http_status()
+    }
+}
+
+//-------------------------------------------------------------------------
+// Error code record
+//-------------------------------------------------------------------------
+
+/// Recorded error code of a report.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorCodeRecord {
+    /// Unique identifier of the error code.
+    pub code: String,
+    /// Category of the error code.
+    pub category: ErrorCategory,
+    /// Exit status of a process terminated by this error.
+    pub exit_status: i32,
+    /// HTTP status of a response reporting this error.
+    pub http_status: u16,
+}
+
+impl ErrorCodeRecord {
+    /// Record an error code.
+    pub fn new(code: &dyn ErrorCode) -> Self {
+        ErrorCodeRecord {
+            code: code.code().to_string(),
+            category: code.category(),
+            exit_status: code.exit_status(),
+            http_status: code.http_status(),
+        }
+    }
+}
+
+impl Display for ErrorCodeRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+impl ErrorCode for ErrorCodeRecord {
+    fn code(&self) -> &str {
+        &self.code
+    }
+
+    fn category(&self) -> ErrorCategory {
+        self.category
+    }
+
+    fn exit_status(&self) -> i32 {
+        self.exit_status
+    }
+
+    fn http_status(&self) -> u16 {
+        self.http_status
+    }
+}
@@ -0,0 +1,243 @@
+//! Configuration of the error reporting mechanism.
+
+use super::handler::{self, HandlerSettings};
+use super::{LocationPolicy, Result, set_location_policy};
+use color_eyre::config::{Frame, HookBuilder, Theme};
+use core::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::Mutex;
+
+/// Whether the error handler is installed.
+static INSTALLED: Mutex<bool> = Mutex::new(false);
+
+//-------------------------------------------------------------------------
+// Color mode
+//-------------------------------------------------------------------------
+
+/// Policy to color error reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Color reports only if the standard error is a terminal and the
+    /// `NO_COLOR` environment variable is not set.
+    #[default]
+    Auto,
+    /// Always color reports.
+    Always,
+    /// Never color reports.
+    Never,
+}
+
+impl ColorMode {
+    /// Check whether reports should be colored.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            ColorMode::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+            }
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+//-------------------------------------------------------------------------
+// Error configuration
+//-------------------------------------------------------------------------
+
+/// Builder to configure and install the error handler of this crate.
+///
+/// It wraps `color_eyre::config::HookBuilder`, which can be further customized
+/// by `ErrorConfig::hook`.
+///
+/// ```no_run
+/// use extlib::error::{ColorMode, ErrorConfig};
+///
+/// ErrorConfig::new()
+///     .color_mode(ColorMode::Never)
+///     .filter_backtrace(["tokio::", "hyper::"])
+///     .footer("Please report this issue to the maintainers.")
+///     .install()
+///     .unwrap();
+/// ```
+pub struct ErrorConfig {
+    hook: HookBuilder,
+    theme: Option<Theme>,
+    color_mode: ColorMode,
+    panic_hook: bool,
+    #[cfg(feature = "tracing")]
+    tracing_subscriber: bool,
+    location_policy: Option<LocationPolicy>,
+    settings: HandlerSettings,
+}
+
+impl Default for ErrorConfig {
+    fn default() -> Self {
+        ErrorConfig::new()
+    }
+}
+
+impl ErrorConfig {
+    /// Create a configuration with the default settings.
+    pub fn new() -> Self {
+        ErrorConfig {
+            hook: HookBuilder::default(),
+            theme: None,
+            color_mode: ColorMode::default(),
+            panic_hook: true,
+            #[cfg(feature = "tracing")]
+            tracing_subscriber: false,
+            location_policy: None,
+            settings: HandlerSettings {
+                display_location_section: true,
+                display_env_section: true,
+                ..HandlerSettings::default()
+            },
+        }
+    }
+
+    /// Customize the wrapped `color_eyre` hook builder.
+    pub fn hook<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(HookBuilder) -> HookBuilder,
+    {
+        self.hook = f(self.hook);
+        self
+    }
+
+    /// Set the theme used when reports are colored.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Set the policy to color reports.
+    pub fn color_mode(mut self, mode: ColorMode) -> Self {
+        self.color_mode = mode;
+        self
+    }
+
+    /// Display the source code location where errors are raised.
+    pub fn display_location_section(mut self, cond: bool) -> Self {
+        self.settings.display_location_section = cond;
+        self
+    }
+
+    /// Set the policy to capture and display locations raising errors,
+    /// which is applied when the handler is installed.
+    pub fn location_policy(mut self, policy: LocationPolicy) -> Self {
+        self.location_policy = Some(policy);
+        self
+    }
+
+    /// Display hints about environment variables to show more information.
+    pub fn display_env_section(mut self, cond: bool) -> Self {
+        self.settings.display_env_section = cond;
+        self
+    }
+
+    /// Capture span traces by default, unless overridden by the
+    /// `RUST_SPANTRACE` environment variable.
+    pub fn capture_span_trace(mut self, cond: bool) -> Self {
+        self.hook = self.hook.capture_span_trace_by_default(cond);
+        self
+    }
+
+    /// Hide backtrace frames whose symbol names contain any of the patterns.
+    pub fn filter_backtrace<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let patterns: Vec<String> = patterns.into_iter().map(Into::into).collect();
+        self.hook = self
+            .hook
+            .add_frame_filter(Box::new(move |frames: &mut Vec<&Frame>| {
+                frames.retain(|frame| {
+                    let name = frame.name.as_deref().unwrap_or_default();
+                    !patterns
+                        .iter()
+                        .any(|pattern| name.contains(pattern.as_str()))
+                });
+            }));
+        self
+    }
+
+    /// Install the panic hook of `color_eyre` to also report panics.
+    ///
+    /// Locations of panics caught by `catch_panic` are recorded regardless of
+    /// this setting.
+    pub fn panic_hook(mut self, cond: bool) -> Self {
+        self.panic_hook = cond;
+        self
+    }
+
+    /// Install a global tracing subscriber formatting events to the standard
+    /// error, with the `tracing_error::ErrorLayer` capturing span traces.
+    ///
+    /// Applications with their own subscriber should rather add the
+    /// `ErrorLayer` to it. An already installed subscriber is kept.
+    #[cfg(feature = "tracing")]
+    pub fn tracing_subscriber(mut self, cond: bool) -> Self {
+        self.tracing_subscriber = cond;
+        self
+    }
+
+    /// Add a section displayed before the error messages.
+    pub fn header<S>(mut self, section: S) -> Self
+    where
+        S: Display + Send + Sync + 'static,
+    {
+        self.settings.headers.push(Box::new(section));
+        self
+    }
+
+    /// Add a section displayed at the end of reports.
+    pub fn footer<S>(mut self, section: S) -> Self
+    where
+        S: Display + Send + Sync + 'static,
+    {
+        self.settings.footers.push(Box::new(section));
+        self
+    }
+
+    /// Add a footer pointing users to an issue tracker.
+    pub fn issue_url(self, url: impl Display) -> Self {
+        self.footer(format!("Please report this issue at: {url}"))
+    }
+
+    /// Install the error handler.
+    ///
+    /// Installing the handler more than once has no effect: the configuration
+    /// of the first installation is kept.
+    pub fn install(self) -> Result<()> {
+        let mut installed = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+        if *installed {
+            return Ok(());
+        }
+        let theme = if self.color_mode.is_enabled() {
+            self.theme.unwrap_or_else(Theme::dark)
+        } else {
+            Theme::new()
+        };
+        if let Some(policy) = self.location_policy {
+            set_location_policy(policy);
+        }
+        #[cfg(feature = "tracing")]
+        if self.tracing_subscriber {
+            use tracing_subscriber::layer::SubscriberExt;
+            use tracing_subscriber::util::SubscriberInitExt;
+            let _ = tracing_subscriber::registry()
+                .with(
+                    tracing_subscriber::fmt::layer()
+                        .with_ansi(self.color_mode.is_enabled())
+                        .with_writer(std::io::stderr),
+                )
+                .with(tracing_error::ErrorLayer::default())
+                .try_init();
+        }
+        let hook = self.hook.theme(theme);
+        handler::install(hook, self.settings, self.panic_hook)?;
+        *installed = true;
+        Ok(())
+    }
+}
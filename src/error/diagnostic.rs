@@ -0,0 +1,402 @@
+//! Diagnostics pointing at spans of user input files.
+
+use core::fmt::{self, Display};
+use std::ops::Range;
+
+//-------------------------------------------------------------------------
+// Label
+//-------------------------------------------------------------------------
+
+/// Labeled byte span of a source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    span: Range<usize>,
+    message: Option<String>,
+    primary: bool,
+}
+
+impl Label {
+    /// Byte span of the label.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Message of the label, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Check whether the label is a primary label, underlined by `^`, or a
+    /// secondary label, underlined by `-`.
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+}
+
+//-------------------------------------------------------------------------
+// Diagnostic
+//-------------------------------------------------------------------------
+
+/// Error pointing at labeled spans of a source text, rendered as a snippet
+/// similar to the diagnostics of `rustc`.
+///
+/// ```
+/// use extlib::error::{Diagnostic, Result};
+/// use extlib::fail;
+///
+/// fn parse(source: &str) -> Result<()> {
+///     let diag = Diagnostic::new("unexpected token", "input.txt", source)
+///         .with_label(8..9, "expected expression");
+///     fail!(diag)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    message: String,
+    source_name: String,
+    source: String,
+    labels: Vec<Label>,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic of a source text.
+    pub fn new(
+        message: impl Display,
+        source_name: impl Display,
+        source: impl Into<String>,
+    ) -> Self {
+        Diagnostic {
+            message: message.to_string(),
+            source_name: source_name.to_string(),
+            source: source.into(),
+            labels: vec![],
+        }
+    }
+
+    /// Add a primary label to a byte span of the source text.
+    pub fn with_label(self, span: Range<usize>, message: impl Display) -> Self {
+        self.add_label(span, Some(message.to_string()), true)
+    }
+
+    /// Add a secondary label to a byte span of the source text.
+    pub fn with_secondary_label(self, span: Range<usize>, message: impl Display) -> Self {
+        self.add_label(span, Some(message.to_string()), false)
+    }
+
+    /// Add a primary label without message to a byte span of the source text.
+    pub fn with_span(self, span: Range<usize>) -> Self {
+        self.add_label(span, None, true)
+    }
+
+    fn add_label(mut self, span: Range<usize>, message: Option<String>, primary: bool) -> Self {
+        self.labels.push(Label {
+            span,
+            message,
+            primary,
+        });
+        self
+    }
+
+    /// Main message of the diagnostic.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Name of the source, such as its file path.
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
+    /// Source text of the diagnostic.
+    pub fn source_text(&self) -> &str {
+        &self.source
+    }
+
+    /// Labels of the diagnostic.
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    /// Compute the 1-based line and column numbers of a byte offset.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let offset = self.floor_char_boundary(offset);
+        let before = &self.source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+
+    /// Clamp a byte offset to the closest preceding char boundary.
+    fn floor_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        offset
+    }
+
+    /// Byte span of the line containing a byte offset, without the newline.
+    fn line_span(&self, offset: usize) -> Range<usize> {
+        let offset = self.floor_char_boundary(offset);
+        let start = self.source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        let end = self.source[offset..]
+            .find('\n')
+            .map_or(self.source.len(), |idx| offset + idx);
+        start..end
+    }
+
+    /// Write the underline of a label on its first line.
+    fn write_underline(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        label: &Label,
+        gutter: usize,
+    ) -> fmt::Result {
+        let start = self.floor_char_boundary(label.span.start);
+        let line = self.line_span(start);
+        // The carriage return of a CRLF line ending is not underlined.
+        let line_end = if self.source[line.clone()].ends_with('\r') {
+            line.end - 1
+        } else {
+            line.end
+        };
+        let end = self
+            .floor_char_boundary(label.span.end)
+            .min(line_end)
+            .max(start);
+        let padding = self.source[line.start..start].chars().count();
+        let width = self.source[start..end].chars().count().max(1);
+        let marker = if label.primary { "^" } else { "-" };
+        write!(
+            f,
+            "\n{:gutter$} | {}{}",
+            "",
+            " ".repeat(padding),
+            marker.repeat(width)
+        )?;
+        if let Some(msg) = &label.message {
+            write!(f, " {msg}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        let mut labels: Vec<&Label> = self.labels.iter().collect();
+        labels.sort_by_key(|label| (label.span.start, !label.primary));
+
+        let primary = labels.iter().find(|label| label.primary).or(labels.first());
+        let Some(primary) = primary else {
+            return write!(f, "\n --> {}", self.source_name);
+        };
+        let (line, column) = self.line_column(primary.span.start);
+        let last_line = labels
+            .iter()
+            .map(|label| self.line_column(label.span.start).0)
+            .max()
+            .unwrap_or(line);
+        let gutter = last_line.to_string().len();
+
+        write!(
+            f,
+            "\n{:gutter$}--> {}:{line}:{column}",
+            "", self.source_name
+        )?;
+        write!(f, "\n{:gutter$} |", "")?;
+
+        let mut previous_line: Option<usize> = None;
+        for label in labels {
+            let (line, _) = self.line_column(label.span.start);
+            if previous_line != Some(line) {
+                if previous_line.is_some_and(|prev| line > prev + 1) {
+                    write!(f, "\n{:gutter$}...", "")?;
+                }
+                let text = self.source[self.line_span(label.span.start)].trim_end_matches('\r');
+                write!(f, "\n{line:>gutter$} | {text}")?;
+                previous_line = Some(line);
+            }
+            self.write_underline(f, label, gutter)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_single_label() {
+        let diag = Diagnostic::new("unexpected token", "input.txt", "let x = ;\n")
+            .with_label(8..9, "expected expression");
+        assert_eq!(
+            diag.to_string(),
+            "unexpected token\n \
+             --> input.txt:1:9\n  \
+             |\n\
+             1 | let x = ;\n  \
+             |         ^ expected expression"
+        );
+    }
+
+    #[test]
+    fn render_without_labels() {
+        let diag = Diagnostic::new("empty input", "input.txt", "");
+        assert_eq!(diag.to_string(), "empty input\n --> input.txt");
+    }
+
+    #[test]
+    fn render_multi_byte_characters() {
+        let source = "nom = \"café\" + ünïcode";
+        let start = source.find('ü').unwrap();
+        let diag = Diagnostic::new("unknown name", "input.txt", source)
+            .with_label(start..source.len(), "not found");
+        assert_eq!(diag.line_column(start), (1, 16));
+        assert_eq!(
+            diag.to_string(),
+            "unknown name\n \
+             --> input.txt:1:16\n  \
+             |\n\
+             1 | nom = \"café\" + ünïcode\n  \
+             |                ^^^^^^^ not found"
+        );
+    }
+
+    #[test]
+    fn render_span_inside_a_character() {
+        let source = "é = 1";
+        let diag = Diagnostic::new("invalid name", "input.txt", source).with_span(1..2);
+        assert_eq!(diag.line_column(1), (1, 1));
+        assert_eq!(
+            diag.to_string(),
+            "invalid name\n \
+             --> input.txt:1:1\n  \
+             |\n\
+             1 | é = 1\n  \
+             | ^"
+        );
+    }
+
+    #[test]
+    fn render_labels_on_non_adjacent_lines() {
+        let source = "fn main() {\n    let x = 1;\n    let y = 2;\n    x = 3;\n}\n";
+        let binding = source.find('x').unwrap();
+        let assignment = source.rfind('x').unwrap();
+        let diag = Diagnostic::new("cannot assign twice", "main.rs", source)
+            .with_secondary_label(binding..binding + 1, "first assignment")
+            .with_label(assignment..assignment + 5, "cannot assign twice");
+        assert_eq!(
+            diag.to_string(),
+            "cannot assign twice\n \
+             --> main.rs:4:5\n  \
+             |\n\
+             2 |     let x = 1;\n  \
+             |         - first assignment\n \
+             ...\n\
+             4 |     x = 3;\n  \
+             |     ^^^^^ cannot assign twice"
+        );
+    }
+
+    #[test]
+    fn render_labels_on_adjacent_and_same_lines() {
+        let source = "a = 1\nb = a + c\n";
+        let diag = Diagnostic::new("unknown variable", "input.txt", source)
+            .with_secondary_label(0..1, "defined here")
+            .with_label(14..15, "not found")
+            .with_secondary_label(10..11, "used here");
+        assert_eq!(
+            diag.to_string(),
+            "unknown variable\n \
+             --> input.txt:2:9\n  \
+             |\n\
+             1 | a = 1\n  \
+             | - defined here\n\
+             2 | b = a + c\n  \
+             |     - used here\n  \
+             |         ^ not found"
+        );
+    }
+
+    #[test]
+    fn render_wide_gutter() {
+        let source = "x\n".repeat(9) + "y = x";
+        let diag = Diagnostic::new("shadowed", "input.txt", source.as_str())
+            .with_secondary_label(0..1, "first")
+            .with_label(18..19, "second");
+        assert_eq!(
+            diag.to_string(),
+            "shadowed\n  \
+             --> input.txt:10:1\n   \
+             |\n \
+             1 | x\n   \
+             | - first\n  \
+             ...\n\
+             10 | y = x\n   \
+             | ^ second"
+        );
+    }
+
+    #[test]
+    fn render_out_of_range_spans() {
+        let source = "key = value\n";
+        let diag = Diagnostic::new("unexpected end of file", "input.txt", source)
+            .with_label(100..120, "expected `;`");
+        assert_eq!(diag.line_column(100), (2, 1));
+        assert_eq!(
+            diag.to_string(),
+            "unexpected end of file\n \
+             --> input.txt:2:1\n  \
+             |\n\
+             2 | \n  \
+             | ^ expected `;`"
+        );
+
+        let diag = Diagnostic::new("invalid value", "input.txt", source)
+            .with_label(6..100, "not a number")
+            .with_secondary_label(Range { start: 4, end: 2 }, "reversed");
+        assert_eq!(
+            diag.to_string(),
+            "invalid value\n \
+             --> input.txt:1:7\n  \
+             |\n\
+             1 | key = value\n  \
+             |     - reversed\n  \
+             |       ^^^^^ not a number"
+        );
+    }
+
+    #[test]
+    fn render_crlf_sources() {
+        let source = "a = 1\r\nb = \r\nc = 3\r\n";
+        let start = source.find("b").unwrap();
+        let diag = Diagnostic::new("missing value", "input.txt", source)
+            .with_label(start..start + 5, "expected a value");
+        assert_eq!(diag.line_column(start), (2, 1));
+        assert_eq!(
+            diag.to_string(),
+            "missing value\n \
+             --> input.txt:2:1\n  \
+             |\n\
+             2 | b = \n  \
+             | ^^^^ expected a value"
+        );
+
+        let end = source.find("\r\nc").unwrap();
+        let diag = Diagnostic::new("missing value", "input.txt", source).with_span(end..end + 2);
+        assert_eq!(
+            diag.to_string(),
+            "missing value\n \
+             --> input.txt:2:5\n  \
+             |\n\
+             2 | b = \n  \
+             |     ^"
+        );
+    }
+}
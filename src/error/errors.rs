@@ -0,0 +1,176 @@
+//! Accumulation of multiple errors before failing.
+
+use super::{
+    ErrorCodeRecord, LocationPolicy, Report, ReportExt, Result, create_error_from, to_report,
+};
+use core::fmt::{self, Display};
+
+//-------------------------------------------------------------------------
+// Error collector
+//-------------------------------------------------------------------------
+
+/// Collector of errors, to report all problems instead of only the first one.
+///
+/// ```
+/// use extlib::error::{Errors, Result};
+/// use extlib::push_error;
+///
+/// fn validate(port: u32, host: &str) -> Result<()> {
+///     let mut errors = Errors::new();
+///     if port > 65535 {
+///         push_error!(errors, "invalid port: {}", port);
+///     }
+///     if host.is_empty() {
+///         push_error!(errors, "empty host");
+///     }
+///     errors.finish()
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Errors {
+    reports: Vec<Report>,
+}
+
+impl Errors {
+    /// Create an empty collector.
+    pub fn new() -> Self {
+        Errors { reports: vec![] }
+    }
+
+    /// Add an error to the collector.
+    #[track_caller]
+    pub fn push(&mut self, error: impl Into<Report>) {
+        self.reports.push(to_report(error));
+    }
+
+    /// Return the value of a result, or add its error to the collector.
+    #[track_caller]
+    pub fn check<T, E>(&mut self, result: Result<T, E>) -> Option<T>
+    where
+        E: Into<Report>,
+    {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.push(err);
+                None
+            }
+        }
+    }
+
+    /// Number of collected errors.
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    /// Check whether no error is collected.
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    /// Collected errors.
+    pub fn reports(&self) -> &[Report] {
+        &self.reports
+    }
+
+    /// Return `Ok(())` if no error is collected, the error itself if only one
+    /// error is collected, or a report of all errors otherwise.
+    ///
+    /// The report of multiple errors can be downcast to `Errors`. Its error
+    /// code, and thus its exit status, is the one of the first collected error
+    /// with an error code.
+    #[track_caller]
+    pub fn finish(self) -> Result<()> {
+        self.into_result(())
+    }
+
+    /// Similar to `finish`, but return `Ok(value)` if no error is collected.
+    #[track_caller]
+    pub fn into_result<T>(mut self, value: T) -> Result<T> {
+        match self.reports.len() {
+            0 => Ok(value),
+            1 => Err(self.reports.remove(0)),
+            _ => {
+                let code = self
+                    .reports
+                    .iter()
+                    .find_map(|report| report.error_code().map(ErrorCodeRecord::new));
+                let report = create_error_from(self);
+                match code {
+                    Some(code) => Err(report.with_code(code)),
+                    None => Err(report),
+                }
+            }
+        }
+    }
+}
+
+impl Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} errors occurred:", self.reports.len())?;
+        let display_location = LocationPolicy::current().displays();
+        for (n, report) in self.reports.iter().enumerate() {
+            write!(f, "\n{:>4}. {report:#}", n + 1)?;
+            if let Some(location) = report.location()
+                && display_location
+            {
+                write!(f, "\n      at {location}")?;
+            }
+            if let Some(code) = report.error_code() {
+                write!(f, "\n      Code: {} ({})", code.code(), code.category())?;
+            }
+            for section in report.help_sections() {
+                write!(f, "\n      {section}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Errors {}
+
+impl Extend<Report> for Errors {
+    fn extend<I: IntoIterator<Item = Report>>(&mut self, iter: I) {
+        self.reports.extend(iter);
+    }
+}
+
+impl IntoIterator for Errors {
+    type Item = Report;
+    type IntoIter = std::vec::IntoIter<Report>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.reports.into_iter()
+    }
+}
+
+//-------------------------------------------------------------------------
+// Utilities
+//-------------------------------------------------------------------------
+
+/// Collect the values of all results, or report all of their errors.
+#[track_caller]
+pub fn try_all<I, T, E>(results: I) -> Result<Vec<T>>
+where
+    I: IntoIterator<Item = Result<T, E>>,
+    E: Into<Report>,
+{
+    let mut errors = Errors::new();
+    let mut values = vec![];
+    for result in results {
+        if let Some(value) = errors.check(result) {
+            values.push(value);
+        }
+    }
+    errors.into_result(values)
+}
+
+/// Create a located error and add it to a collector of errors.
+///
+/// The arguments following the collector are the same as of `fail!`.
+#[macro_export]
+macro_rules! push_error {
+    ($errors:expr, $($arg:tt)+) => {
+        $crate::error::Errors::push(&mut $errors, $crate::__create_error!($($arg)+))
+    };
+}
@@ -0,0 +1,49 @@
+//! Entry points of programs reporting errors consistently.
+
+use super::{ErrorConfig, Report, ReportExt, Result};
+use std::process::ExitCode;
+
+/// Run the main function of a program: install the error handler, print the
+/// report of any returned error and derive the exit code from it.
+///
+/// ```no_run
+/// use std::process::ExitCode;
+///
+/// fn main() -> ExitCode {
+///     extlib::error::run_main(|| {
+///         // ...
+///         Ok(())
+///     })
+/// }
+/// ```
+pub fn run_main<F>(f: F) -> ExitCode
+where
+    F: FnOnce() -> Result<()>,
+{
+    run_main_with(ErrorConfig::new(), f)
+}
+
+/// Similar to `run_main`, but the error handler is installed with `config`.
+pub fn run_main_with<F>(config: ErrorConfig, f: F) -> ExitCode
+where
+    F: FnOnce() -> Result<()>,
+{
+    if let Err(err) = config.install() {
+        eprintln!("Warning: failed to install the error handler: {err}");
+    }
+    match f() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(report) => {
+            eprintln!("Error: {report:?}");
+            exit_code(&report)
+        }
+    }
+}
+
+/// Convert the exit status of a report to an exit code.
+fn exit_code(report: &Report) -> ExitCode {
+    match u8::try_from(report.exit_status()) {
+        Ok(0) | Err(_) => ExitCode::FAILURE,
+        Ok(status) => ExitCode::from(status),
+    }
+}
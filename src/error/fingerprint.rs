@@ -0,0 +1,531 @@
+//! Fingerprints of reports, to group and deduplicate similar errors.
+
+use super::{Report, ReportExt, Result, SourceLocation, to_report};
+use core::fmt::{self, Display};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Placeholder of the variable parts of message templates.
+const PLACEHOLDER: &str = "<*>";
+
+/// Default number of sample arguments kept by each group of errors.
+const DEFAULT_MAX_SAMPLES: usize = 3;
+
+//-------------------------------------------------------------------------
+// Fingerprint
+//-------------------------------------------------------------------------
+
+/// Stable fingerprint of a report, derived from its error code, the template
+/// of its message and the location where it was raised.
+///
+/// The message template is the message whose variable parts, such as
+/// numbers, paths and quoted strings, are replaced by `<*>`. Reports raised
+/// by the same `fail!` with different arguments thus have the same
+/// fingerprint, which is stable across runs and builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint(pub u64);
+
+impl Fingerprint {
+    /// Compute the fingerprint of a report.
+    pub fn of(report: &Report) -> Self {
+        let (template, _) = message_template(&report.to_string());
+        let code = report.error_code().map(|code| code.code().to_string());
+        Fingerprint::from_parts(code.as_deref(), &template, report.location())
+    }
+
+    /// Compute a fingerprint from an error code, a message template and a
+    /// location.
+    pub fn from_parts(
+        code: Option<&str>,
+        template: &str,
+        location: Option<&SourceLocation>,
+    ) -> Self {
+        let mut hasher = Fnv1a::new();
+        hasher.write(code.unwrap_or_default().as_bytes());
+        hasher.write(template.as_bytes());
+        if let Some(location) = location {
+            hasher.write(location.file().as_bytes());
+            hasher.write(&location.line().to_le_bytes());
+            hasher.write(&location.column().to_le_bytes());
+        }
+        Fingerprint(hasher.finish())
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// FNV-1a hasher, whose output is stable unlike `DefaultHasher`.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Hash bytes followed by a separator, so that consecutive parts are not
+    /// ambiguous.
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().chain([&0xff]) {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Split a message into its template, where variable parts are replaced by
+/// `<*>`, and the variable parts.
+///
+/// Variable parts are quoted strings, and words containing digits or path
+/// separators, without their surrounding brackets and trailing punctuation.
+pub fn message_template(message: &str) -> (String, Vec<String>) {
+    let mut template = String::with_capacity(message.len());
+    let mut args = vec![];
+    let mut rest = message;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            template.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if matches!(c, '"' | '\'' | '`')
+            && let Some(end) = rest[1..].find(c)
+        {
+            template.push(c);
+            template.push_str(PLACEHOLDER);
+            template.push(c);
+            args.push(rest[1..end + 1].to_string());
+            rest = &rest[end + 2..];
+            continue;
+        }
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..len];
+        let core = word.trim_end_matches(['.', ',', ':', ';', ')', ']', '!', '?']);
+        let prefix_len = core.len() - core.trim_start_matches(['(', '[']).len();
+        let (prefix, core) = core.split_at(prefix_len);
+        if core.contains(|c: char| c.is_ascii_digit() || c == '/' || c == '\\') {
+            template.push_str(prefix);
+            template.push_str(PLACEHOLDER);
+            template.push_str(&word[prefix_len + core.len()..]);
+            args.push(core.to_string());
+        } else {
+            template.push_str(word);
+        }
+        rest = &rest[len..];
+    }
+    (template, args)
+}
+
+//-------------------------------------------------------------------------
+// Error group
+//-------------------------------------------------------------------------
+
+/// Group of reports with the same fingerprint.
+#[derive(Debug, Clone)]
+pub struct ErrorGroup {
+    fingerprint: Fingerprint,
+    template: String,
+    code: Option<String>,
+    location: Option<SourceLocation>,
+    count: usize,
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+    first_index: usize,
+    last_index: usize,
+    samples: Vec<Vec<String>>,
+}
+
+impl ErrorGroup {
+    /// Fingerprint of the reports of the group.
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint
+    }
+
+    /// Message template of the reports of the group.
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    /// Error code of the reports of the group.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Location where the reports of the group were raised.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+
+    /// Number of reports of the group.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Time when the first report of the group was added.
+    pub fn first_seen(&self) -> SystemTime {
+        self.first_seen
+    }
+
+    /// Time when the last report of the group was added.
+    pub fn last_seen(&self) -> SystemTime {
+        self.last_seen
+    }
+
+    /// Index of the first report of the group among all added reports.
+    pub fn first_index(&self) -> usize {
+        self.first_index
+    }
+
+    /// Index of the last report of the group among all added reports.
+    pub fn last_index(&self) -> usize {
+        self.last_index
+    }
+
+    /// Variable parts of the messages of the first reports of the group.
+    pub fn samples(&self) -> &[Vec<String>] {
+        &self.samples
+    }
+}
+
+//-------------------------------------------------------------------------
+// Error aggregator
+//-------------------------------------------------------------------------
+
+/// Aggregator grouping reports by fingerprint, to summarize the errors of
+/// batch jobs.
+///
+/// ```
+/// use extlib::error::{ErrorAggregator, Result};
+/// use extlib::fail;
+///
+/// fn process(item: u32) -> Result<u32> {
+///     if item % 2 == 1 {
+///         fail!("Invalid item {item}");
+///     }
+///     Ok(item)
+/// }
+///
+/// let mut errors = ErrorAggregator::new();
+/// let values: Vec<u32> = (0..10).filter_map(|item| errors.check(process(item))).collect();
+/// assert_eq!(errors.total(), 5);
+/// assert_eq!(errors.groups().len(), 1);
+/// println!("{errors}");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ErrorAggregator {
+    groups: Vec<ErrorGroup>,
+    index: HashMap<Fingerprint, usize>,
+    total: usize,
+    max_samples: usize,
+}
+
+impl Default for ErrorAggregator {
+    fn default() -> Self {
+        ErrorAggregator::new()
+    }
+}
+
+impl ErrorAggregator {
+    /// Create an empty aggregator.
+    pub fn new() -> Self {
+        ErrorAggregator {
+            groups: vec![],
+            index: HashMap::new(),
+            total: 0,
+            max_samples: DEFAULT_MAX_SAMPLES,
+        }
+    }
+
+    /// Set the number of sample arguments kept by each group.
+    pub fn max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = max_samples;
+        self
+    }
+
+    /// Add a report, and return its fingerprint.
+    pub fn add(&mut self, report: &Report) -> Fingerprint {
+        let message = report.to_string();
+        let (template, args) = message_template(&message);
+        let code = report.error_code().map(|code| code.code().to_string());
+        let location = report.location();
+        let fingerprint = Fingerprint::from_parts(code.as_deref(), &template, location);
+
+        let now = SystemTime::now();
+        let index = self.total;
+        self.total += 1;
+        let idx = *self.index.entry(fingerprint).or_insert_with(|| {
+            self.groups.push(ErrorGroup {
+                fingerprint,
+                template,
+                code,
+                location: location.cloned(),
+                count: 0,
+                first_seen: now,
+                last_seen: now,
+                first_index: index,
+                last_index: index,
+                samples: vec![],
+            });
+            self.groups.len() - 1
+        });
+        let group = &mut self.groups[idx];
+        group.count += 1;
+        group.last_seen = now;
+        group.last_index = index;
+        if group.samples.len() < self.max_samples && !args.is_empty() {
+            group.samples.push(args);
+        }
+        fingerprint
+    }
+
+    /// Return the value of a result, or add its error to the aggregator.
+    #[track_caller]
+    pub fn check<T, E>(&mut self, result: Result<T, E>) -> Option<T>
+    where
+        E: Into<Report>,
+    {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.add(&to_report(err));
+                None
+            }
+        }
+    }
+
+    /// Groups of reports, by order of first occurrence.
+    pub fn groups(&self) -> &[ErrorGroup] {
+        &self.groups
+    }
+
+    /// Group of a fingerprint, if any.
+    pub fn group(&self, fingerprint: Fingerprint) -> Option<&ErrorGroup> {
+        self.index.get(&fingerprint).map(|&idx| &self.groups[idx])
+    }
+
+    /// Total number of added reports.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Check whether no report was added.
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+}
+
+impl Extend<Report> for ErrorAggregator {
+    fn extend<I: IntoIterator<Item = Report>>(&mut self, iter: I) {
+        for report in iter {
+            self.add(&report);
+        }
+    }
+}
+
+/// Render a summary table of the groups, by decreasing number of reports.
+impl Display for ErrorAggregator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut groups: Vec<&ErrorGroup> = self.groups.iter().collect();
+        groups.sort_by_key(|group| (std::cmp::Reverse(group.count), group.first_index));
+
+        let header = ["Fingerprint", "Count", "Code", "Location", "Message"];
+        let rows: Vec<[String; 5]> = groups
+            .iter()
+            .map(|group| {
+                [
+                    group.fingerprint.to_string(),
+                    group.count.to_string(),
+                    group.code.clone().unwrap_or_else(|| "-".to_string()),
+                    group
+                        .location
+                        .as_ref()
+                        .map_or_else(|| "-".to_string(), |loc| loc.to_string()),
+                    group.template.clone(),
+                ]
+            })
+            .collect();
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        write!(f, "{} errors in {} groups:", self.total, self.groups.len())?;
+        let write_row = |f: &mut fmt::Formatter<'_>, cells: [&str; 5]| {
+            write!(
+                f,
+                "\n{:<w0$}  {:>w1$}  {:<w2$}  {:<w3$}  {}",
+                cells[0],
+                cells[1],
+                cells[2],
+                cells[3],
+                cells[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3]
+            )
+        };
+        write_row(f, header)?;
+        for (row, group) in rows.iter().zip(&groups) {
+            write_row(f, row.each_ref().map(String::as_str))?;
+            for sample in &group.samples {
+                write!(f, "\n{:w$}  e.g. {}", "", sample.join(", "), w = widths[0])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LocationPolicy;
+
+    fn template(message: &str) -> (String, Vec<String>) {
+        message_template(message)
+    }
+
+    #[test]
+    fn template_replaces_quoted_strings() {
+        assert_eq!(
+            template(r#"Cannot open "my file.txt" in 'dir' or `cache`"#),
+            (
+                r#"Cannot open "<*>" in '<*>' or `<*>`"#.to_string(),
+                vec!["my file.txt".into(), "dir".into(), "cache".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn template_keeps_unclosed_quotes() {
+        assert_eq!(
+            template(r#"Unexpected " in input"#),
+            (r#"Unexpected " in input"#.to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn template_keeps_apostrophes_inside_words() {
+        assert_eq!(
+            template("Can't read the user's config"),
+            ("Can't read the user's config".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn template_keeps_trailing_punctuation() {
+        assert_eq!(
+            template("Invalid items 42, 7 and (3): expected 10."),
+            (
+                "Invalid items <*>, <*> and (<*>): expected <*>.".to_string(),
+                vec!["42".into(), "7".into(), "3".into(), "10".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn template_replaces_words_with_digits_and_paths() {
+        assert_eq!(
+            template("User user42 cannot read /etc/app.toml or C:\\app\\config"),
+            (
+                "User <*> cannot read <*> or <*>".to_string(),
+                vec![
+                    "user42".into(),
+                    "/etc/app.toml".into(),
+                    "C:\\app\\config".into()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn template_keeps_whitespaces() {
+        assert_eq!(
+            template("  Line 3\n\tfailed "),
+            ("  Line <*>\n\tfailed ".to_string(), vec!["3".into()])
+        );
+    }
+
+    fn process(item: u32) -> Result<()> {
+        crate::fail!("Invalid item {item} in /tmp/batch-{item}.json");
+    }
+
+    fn process_other(item: u32) -> Result<()> {
+        crate::fail!("Invalid item {item} in /tmp/batch-{item}.json");
+    }
+
+    #[test]
+    fn same_site_with_different_arguments_has_same_fingerprint() {
+        let first = process(1).unwrap_err();
+        let second = process(20).unwrap_err();
+        assert_ne!(first.to_string(), second.to_string());
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        // Sites of the same message differ only by their locations.
+        assert_eq!(
+            first.fingerprint() != process_other(1).unwrap_err().fingerprint(),
+            LocationPolicy::current().captures()
+        );
+    }
+
+    #[test]
+    fn fingerprints_depend_on_all_parts() {
+        let location = SourceLocation::new("src/main.rs", 3, 5);
+        let fingerprint = Fingerprint::from_parts(Some("E1"), "a <*>", Some(&location));
+        assert_eq!(
+            fingerprint,
+            Fingerprint::from_parts(Some("E1"), "a <*>", Some(&location))
+        );
+        assert_ne!(
+            fingerprint,
+            Fingerprint::from_parts(None, "a <*>", Some(&location))
+        );
+        assert_ne!(
+            fingerprint,
+            Fingerprint::from_parts(Some("E1"), "b <*>", Some(&location))
+        );
+        assert_ne!(
+            fingerprint,
+            Fingerprint::from_parts(Some("E1"), "a <*>", None)
+        );
+        // Parts are separated, so that moving text between them changes the
+        // fingerprint.
+        assert_ne!(
+            Fingerprint::from_parts(Some("ab"), "c", None),
+            Fingerprint::from_parts(Some("a"), "bc", None)
+        );
+        assert_eq!(fingerprint.to_string().len(), 16);
+    }
+
+    #[test]
+    fn aggregator_groups_reports_by_fingerprint() {
+        let mut errors = ErrorAggregator::new().max_samples(2);
+        for item in 0..5 {
+            errors.check(process(item));
+        }
+        errors.check(Err::<(), _>(crate::err!("Missing batch /tmp/batch-0.json")));
+        assert_eq!(errors.total(), 6);
+        assert_eq!(errors.groups().len(), 2);
+        let group = &errors.groups()[0];
+        assert_eq!(group.count(), 5);
+        assert_eq!(group.template(), "Invalid item <*> in <*>");
+        assert_eq!(group.first_index(), 0);
+        assert_eq!(group.last_index(), 4);
+        assert_eq!(
+            group.samples(),
+            [
+                vec!["0".to_string(), "/tmp/batch-0.json".to_string()],
+                vec!["1".to_string(), "/tmp/batch-1.json".to_string()]
+            ]
+        );
+    }
+}
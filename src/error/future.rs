@@ -0,0 +1,203 @@
+//! Location-preserving contexts of futures and streams.
+//!
+//! `#[track_caller]` does not flow through `.await`, so the location where a
+//! future or a stream is created is recorded eagerly and attached to the
+//! reports it yields.
+
+use super::{Report, ReportExt, Result, SourceLocation, add_context_at, handler, to_report};
+use core::fmt::{Debug, Display};
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::Stream;
+use pin_project_lite::pin_project;
+
+//-------------------------------------------------------------------------
+// Adaptor
+//-------------------------------------------------------------------------
+
+pin_project! {
+    /// Future or stream attaching a context frame to the reports it yields.
+    ///
+    /// Created by `FutureExt::context_here`, `FutureExt::in_context` and their
+    /// `StreamExt` equivalents.
+    #[derive(Debug)]
+    #[must_use = "futures and streams do nothing unless polled"]
+    pub struct InContext<F, M> {
+        #[pin]
+        inner: F,
+        message: Option<M>,
+        location: SourceLocation,
+    }
+}
+
+impl<F, M> InContext<F, M> {
+    /// Source code location where the adaptor was created.
+    pub fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+}
+
+impl<F, T, E, M> Future for InContext<F, M>
+where
+    F: Future<Output = core::result::Result<T, E>>,
+    E: Into<Report>,
+    M: Debug + Display + Send + Sync + 'static,
+{
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.inner.poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Ok(value)) => Poll::Ready(Ok(value)),
+            Poll::Ready(Err(err)) => {
+                let report = into_report(err, this.location);
+                Poll::Ready(Err(add_context_at(
+                    report,
+                    this.message.take(),
+                    this.location.clone(),
+                )))
+            }
+        }
+    }
+}
+
+impl<S, T, E, M> Stream for InContext<S, M>
+where
+    S: Stream<Item = core::result::Result<T, E>>,
+    E: Into<Report>,
+    M: Clone + Debug + Display + Send + Sync + 'static,
+{
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        match this.inner.poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(Some(Ok(value))) => Poll::Ready(Some(Ok(value))),
+            Poll::Ready(Some(Err(err))) => {
+                let report = into_report(err, this.location);
+                Poll::Ready(Some(Err(add_context_at(
+                    report,
+                    this.message.clone(),
+                    this.location.clone(),
+                ))))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Convert an error to a report, located at the creation of the adaptor if
+/// the report is created by the conversion.
+fn into_report<E: Into<Report>>(err: E, location: &SourceLocation) -> Report {
+    let (report, here) = convert(err);
+    if report.location() == Some(&here) {
+        handler::update_metadata(report, |metadata| {
+            metadata.location = Some(location.clone())
+        })
+    } else {
+        report
+    }
+}
+
+/// Convert an error to a report, and return the location of the conversion.
+#[track_caller]
+fn convert<E: Into<Report>>(err: E) -> (Report, SourceLocation) {
+    (to_report(err), SourceLocation::caller())
+}
+
+//-------------------------------------------------------------------------
+// Futures
+//-------------------------------------------------------------------------
+
+/// Trait to add contexts to futures returning results.
+///
+/// ```
+/// use extlib::error::{FutureExt, Result};
+///
+/// async fn load(path: &str) -> Result<String> {
+///     Ok(std::fs::read_to_string(path)?)
+/// }
+///
+/// async fn run() -> Result<String> {
+///     load("config.toml").in_context("Failed to load the config").await
+/// }
+/// ```
+pub trait FutureExt: Sized {
+    /// Record the caller's location, and attach it to the error returned by
+    /// the future, without adding a context message.
+    #[track_caller]
+    fn context_here(self) -> InContext<Self, String> {
+        InContext {
+            inner: self,
+            message: None,
+            location: SourceLocation::caller(),
+        }
+    }
+
+    /// Record the caller's location, and wrap the error returned by the
+    /// future with a context message at this location.
+    #[track_caller]
+    fn in_context<M>(self, message: M) -> InContext<Self, M>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+    {
+        InContext {
+            inner: self,
+            message: Some(message),
+            location: SourceLocation::caller(),
+        }
+    }
+}
+
+impl<F, T, E> FutureExt for F
+where
+    F: Future<Output = core::result::Result<T, E>>,
+    E: Into<Report>,
+{
+}
+
+//-------------------------------------------------------------------------
+// Streams
+//-------------------------------------------------------------------------
+
+/// Trait to add contexts to streams yielding results.
+pub trait StreamExt: Sized {
+    /// Record the caller's location, and attach it to every error yielded by
+    /// the stream, without adding a context message.
+    #[track_caller]
+    fn context_here(self) -> InContext<Self, String> {
+        InContext {
+            inner: self,
+            message: None,
+            location: SourceLocation::caller(),
+        }
+    }
+
+    /// Record the caller's location, and wrap every error yielded by the
+    /// stream with a context message at this location.
+    #[track_caller]
+    fn in_context<M>(self, message: M) -> InContext<Self, M>
+    where
+        M: Clone + Debug + Display + Send + Sync + 'static,
+    {
+        InContext {
+            inner: self,
+            message: Some(message),
+            location: SourceLocation::caller(),
+        }
+    }
+}
+
+impl<S, T, E> StreamExt for S
+where
+    S: Stream<Item = core::result::Result<T, E>>,
+    E: Into<Report>,
+{
+}
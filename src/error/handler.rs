@@ -0,0 +1,558 @@
+//! Custom `eyre` handler recording typed metadata of reports.
+
+use super::{ErrorCode, LocationPolicy, Report, panic};
+use backtrace::Backtrace;
+use color_eyre::config::HookBuilder;
+use color_eyre::eyre::{self, EyreHandler};
+use core::fmt::{self, Debug, Display};
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::panic::Location;
+use std::sync::{Arc, LazyLock, OnceLock, PoisonError, RwLock};
+#[cfg(feature = "tracing")]
+use tracing_error::{SpanTrace, SpanTraceStatus};
+
+//-------------------------------------------------------------------------
+// Source code location
+//-------------------------------------------------------------------------
+
+/// Source code location where an error was raised.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLocation {
+    file: Cow<'static, str>,
+    line: u32,
+    column: u32,
+}
+
+impl SourceLocation {
+    /// Create a new source code location.
+    pub fn new(file: impl Into<Cow<'static, str>>, line: u32, column: u32) -> Self {
+        SourceLocation {
+            file: file.into(),
+            line,
+            column,
+        }
+    }
+
+    /// Capture the source code location of the caller.
+    #[track_caller]
+    pub fn caller() -> Self {
+        Location::caller().into()
+    }
+
+    /// File containing the location.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Line number of the location.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Column number of the location.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl From<&'static Location<'static>> for SourceLocation {
+    fn from(loc: &'static Location<'static>) -> Self {
+        SourceLocation::new(loc.file(), loc.line(), loc.column())
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Context frame
+//-------------------------------------------------------------------------
+
+/// Frame of context added to a report while it is propagated to callers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContextFrame {
+    message: Option<String>,
+    location: SourceLocation,
+}
+
+impl ContextFrame {
+    /// Create a new context frame.
+    pub fn new(message: Option<String>, location: SourceLocation) -> Self {
+        ContextFrame { message, location }
+    }
+
+    /// Context message of the frame, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Source code location where the context was added.
+    pub fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+}
+
+//-------------------------------------------------------------------------
+// Help section
+//-------------------------------------------------------------------------
+
+/// Kind of help sections attached to reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HelpKind {
+    /// Additional information about the error.
+    Note,
+    /// Warning about a possible problem related to the error.
+    Warning,
+    /// Suggestion to fix the error.
+    Help,
+}
+
+impl Display for HelpKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HelpKind::Note => write!(f, "Note"),
+            HelpKind::Warning => write!(f, "Warning"),
+            HelpKind::Help => write!(f, "Help"),
+        }
+    }
+}
+
+/// Note, warning or suggestion attached to a report.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HelpSection {
+    kind: HelpKind,
+    message: String,
+}
+
+impl HelpSection {
+    /// Create a new help section.
+    pub fn new(kind: HelpKind, message: String) -> Self {
+        HelpSection { kind, message }
+    }
+
+    /// Kind of the section.
+    pub fn kind(&self) -> HelpKind {
+        self.kind
+    }
+
+    /// Message of the section.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for HelpSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Metadata
+//-------------------------------------------------------------------------
+
+/// Typed metadata recorded by reports.
+#[derive(Debug, Default)]
+pub(crate) struct Metadata {
+    pub(crate) location: Option<SourceLocation>,
+    pub(crate) contexts: Vec<ContextFrame>,
+    pub(crate) helps: Vec<HelpSection>,
+    pub(crate) code: Option<Box<dyn ErrorCode>>,
+    #[cfg(feature = "tracing")]
+    pub(crate) span_trace: Option<SpanTrace>,
+}
+
+/// Capture the current span trace, if supported by the tracing subscriber in
+/// effect.
+#[cfg(feature = "tracing")]
+pub(crate) fn capture_span_trace() -> Option<SpanTrace> {
+    let span_trace = SpanTrace::capture();
+    (span_trace.status() == SpanTraceStatus::CAPTURED).then_some(span_trace)
+}
+
+/// Error carrying the metadata of a report whose handler is not `Handler`,
+/// which happens when another `eyre` hook was set before the one of this
+/// crate, such as by `color_eyre::install` or by a report created by `?`
+/// before any report of this crate.
+///
+/// It is the error of reports created from messages, and otherwise takes the
+/// place of the outermost error of reports, displaying its message and
+/// returning its source, so that the chain of the report is unchanged. The
+/// outermost error is then found by `ReportExt::find_cause`, but no longer by
+/// `Report::downcast_ref`. Such metadata are still returned by `ReportExt`,
+/// but not rendered by the foreign handler.
+pub(crate) struct Annotated {
+    message: String,
+    report: Option<Report>,
+    metadata: Metadata,
+}
+
+impl Annotated {
+    pub(crate) fn new(message: String) -> Self {
+        Annotated {
+            message,
+            report: None,
+            metadata: Metadata::default(),
+        }
+    }
+
+    fn wrap(report: Report) -> Self {
+        Annotated {
+            message: String::new(),
+            report: Some(report),
+            metadata: Metadata::default(),
+        }
+    }
+}
+
+impl Debug for Annotated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Annotated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.report {
+            Some(report) => Display::fmt(&**report, f),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl StdError for Annotated {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.report.as_ref().and_then(|report| report.source())
+    }
+}
+
+/// Return the outermost error of a report wrapped by an `Annotated` error of
+/// its chain, or the error itself otherwise.
+pub(crate) fn unannotated<'a>(error: &'a (dyn StdError + 'static)) -> &'a (dyn StdError + 'static) {
+    match error.downcast_ref::<Annotated>() {
+        Some(Annotated {
+            report: Some(report),
+            ..
+        }) => &**report,
+        _ => error,
+    }
+}
+
+/// Metadata of a report, if any was recorded.
+pub(crate) fn metadata(report: &Report) -> Option<&Metadata> {
+    match report.handler().downcast_ref::<Handler>() {
+        Some(handler) => Some(&handler.metadata),
+        None => report
+            .downcast_ref::<Annotated>()
+            .map(|annotated| &annotated.metadata),
+    }
+}
+
+/// Update the metadata of a report, which are carried by an `Annotated`
+/// error if its handler is not `Handler`.
+pub(crate) fn update_metadata(mut report: Report, f: impl FnOnce(&mut Metadata)) -> Report {
+    if let Some(handler) = report.handler_mut().downcast_mut::<Handler>() {
+        f(&mut handler.metadata);
+        return report;
+    }
+    if let Some(annotated) = report.downcast_mut::<Annotated>() {
+        f(&mut annotated.metadata);
+        return report;
+    }
+    let mut annotated = Annotated::wrap(report);
+    f(&mut annotated.metadata);
+    Report::new(annotated)
+}
+
+//-------------------------------------------------------------------------
+// Error handler
+//-------------------------------------------------------------------------
+
+/// Error handler attached to every report once the hook of this crate is
+/// set, which is done by the first report raised by this crate or by
+/// `ErrorConfig::install`.
+///
+/// Once `ErrorConfig::install` is called, it wraps the handler of
+/// `color_eyre`, which still renders the error chain, span trace and
+/// backtrace, and adds the typed sections of this crate. Until then, it wraps
+/// the default handler of `eyre`.
+pub struct Handler {
+    inner: Box<dyn EyreHandler>,
+    metadata: Metadata,
+    backtrace: Option<Backtrace>,
+    settings: Arc<HandlerSettings>,
+}
+
+/// Display settings shared by all handlers created by the same hook.
+#[derive(Default)]
+pub(crate) struct HandlerSettings {
+    pub(crate) display_location_section: bool,
+    pub(crate) display_env_section: bool,
+    pub(crate) headers: Vec<Box<dyn Display + Send + Sync>>,
+    pub(crate) footers: Vec<Box<dyn Display + Send + Sync>>,
+}
+
+impl Handler {
+    pub(crate) fn new(inner: Box<dyn EyreHandler>, settings: Arc<HandlerSettings>) -> Self {
+        Handler {
+            inner,
+            metadata: Metadata::default(),
+            backtrace: None,
+            settings,
+        }
+    }
+
+    /// Source code location where the error was raised.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.metadata.location.as_ref()
+    }
+
+    /// Set the source code location where the error was raised.
+    pub fn set_location(&mut self, location: SourceLocation) {
+        self.metadata.location = Some(location);
+    }
+
+    /// Forget the source code location where the error was raised.
+    pub(crate) fn clear_location(&mut self) {
+        self.metadata.location = None;
+    }
+
+    /// Context frames of the error, from the innermost to the outermost.
+    pub fn contexts(&self) -> &[ContextFrame] {
+        &self.metadata.contexts
+    }
+
+    /// Add a new outermost context frame.
+    pub fn push_context(&mut self, frame: ContextFrame) {
+        self.metadata.contexts.push(frame);
+    }
+
+    /// Notes, warnings and suggestions attached to the error.
+    pub fn help_sections(&self) -> &[HelpSection] {
+        &self.metadata.helps
+    }
+
+    /// Attach a note, warning or suggestion to the error.
+    pub fn push_help(&mut self, section: HelpSection) {
+        self.metadata.helps.push(section);
+    }
+
+    /// Error code attached to the error.
+    pub fn error_code(&self) -> Option<&dyn ErrorCode> {
+        self.metadata.code.as_deref()
+    }
+
+    /// Attach an error code to the error.
+    pub fn set_error_code(&mut self, code: impl ErrorCode) {
+        self.metadata.code = Some(Box::new(code));
+    }
+
+    /// Return the wrapped `color_eyre` handler, if any.
+    pub fn color_eyre_handler(&self) -> Option<&color_eyre::Handler> {
+        self.inner.downcast_ref::<color_eyre::Handler>()
+    }
+
+    /// Span trace captured when the error was raised, either by the wrapped
+    /// `color_eyre` handler or by this crate.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span_trace(&self) -> Option<&SpanTrace> {
+        self.color_eyre_handler()
+            .and_then(|handler| handler.span_trace())
+            .or(self.metadata.span_trace.as_ref())
+    }
+
+    /// Backtrace captured when the error was raised, either by the wrapped
+    /// `color_eyre` handler or, until `ErrorConfig::install` is called, by this
+    /// handler. It is not resolved if captured by this handler.
+    pub(crate) fn backtrace(&self) -> Option<&Backtrace> {
+        match self.color_eyre_handler() {
+            Some(handler) => handler.backtrace(),
+            None => self.backtrace.as_ref(),
+        }
+    }
+
+    /// Write hints to display more information, similar to `color_eyre`.
+    fn write_env_section(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let has_backtrace = self
+            .color_eyre_handler()
+            .is_some_and(|handler| handler.backtrace().is_some());
+        if !self.settings.display_env_section || has_backtrace {
+            return Ok(());
+        }
+        write!(
+            f,
+            "\n\nBacktrace omitted. Run with RUST_BACKTRACE=1 environment \
+             variable to display it.\n\
+             Run with RUST_BACKTRACE=full to include source snippets."
+        )
+    }
+}
+
+impl EyreHandler for Handler {
+    fn debug(&self, error: &(dyn StdError + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            return self.inner.debug(error, f);
+        }
+        for header in &self.settings.headers {
+            write!(f, "\n{header}")?;
+        }
+        self.inner.debug(error, f)?;
+        if let Some(code) = &self.metadata.code {
+            write!(f, "\n\nCode:\n   {} ({})", code.code(), code.category())?;
+        }
+        let display_location =
+            self.settings.display_location_section && LocationPolicy::current().displays();
+        if let Some(location) = &self.metadata.location
+            && display_location
+        {
+            write!(f, "\n\nLocation:\n   {location}")?;
+        }
+        if !self.metadata.contexts.is_empty() && display_location {
+            write!(f, "\n\nContext:")?;
+            for (n, frame) in self.metadata.contexts.iter().enumerate() {
+                match frame.message() {
+                    Some(msg) => write!(f, "\n{n:>4}: {msg}\n      at {}", frame.location())?,
+                    None => write!(f, "\n{n:>4}: at {}", frame.location())?,
+                }
+            }
+        }
+        // Span traces captured by the wrapped `color_eyre` handler are
+        // already rendered by it.
+        #[cfg(feature = "tracing")]
+        if let Some(span_trace) = &self.metadata.span_trace
+            && self
+                .color_eyre_handler()
+                .is_none_or(|handler| handler.span_trace().is_none())
+        {
+            write!(f, "\n\nSpan trace:\n{span_trace}")?;
+        }
+        if !self.metadata.helps.is_empty() {
+            writeln!(f)?;
+            for section in &self.metadata.helps {
+                write!(f, "\n{section}")?;
+            }
+        }
+        self.write_env_section(f)?;
+        for footer in &self.settings.footers {
+            write!(f, "\n\n{footer}")?;
+        }
+        Ok(())
+    }
+
+    fn display(&self, error: &(dyn StdError + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.display(error, f)
+    }
+
+    fn track_caller(&mut self, location: &'static Location<'static>) {
+        // The location is not forwarded to the wrapped handler, which would
+        // render it a second time.
+        if LocationPolicy::current().captures() {
+            self.metadata.location = Some(location.into());
+        }
+    }
+}
+
+//-------------------------------------------------------------------------
+// Hook
+//-------------------------------------------------------------------------
+
+/// Hook creating the handlers wrapped by `Handler`.
+type InnerHook = Box<dyn Fn(&(dyn StdError + 'static)) -> Box<dyn EyreHandler> + Send + Sync>;
+
+/// Configuration installed by `ErrorConfig::install`.
+struct HookConfig {
+    inner: InnerHook,
+    settings: Arc<HandlerSettings>,
+}
+
+/// Configuration of the handlers, if installed.
+static CONFIG: RwLock<Option<HookConfig>> = RwLock::new(None);
+
+/// Settings of the handlers until a configuration is installed.
+static DEFAULT_SETTINGS: LazyLock<Arc<HandlerSettings>> = LazyLock::new(|| {
+    Arc::new(HandlerSettings {
+        display_location_section: true,
+        ..HandlerSettings::default()
+    })
+});
+
+/// Whether the hook of this crate is the `eyre` hook.
+static HOOK: OnceLock<bool> = OnceLock::new();
+
+/// Set the hook of this crate as the `eyre` hook unless already done, and
+/// return whether it is the `eyre` hook.
+///
+/// It is called when reports are raised by this crate, so that their
+/// metadata are recorded even if `ErrorConfig::install` is never called. It
+/// fails if another hook was set before, either explicitly or by `eyre` when
+/// a report was created before any report of this crate.
+pub(crate) fn ensure_hook() -> bool {
+    *HOOK.get_or_init(|| eyre::set_hook(Box::new(create_handler)).is_ok())
+}
+
+/// Create the handler of a new report.
+fn create_handler(error: &(dyn StdError + 'static)) -> Box<dyn EyreHandler> {
+    let config = CONFIG.read().unwrap_or_else(PoisonError::into_inner);
+    let handler = match &*config {
+        Some(config) => Handler::new((config.inner)(error), config.settings.clone()),
+        None => {
+            let mut handler = Handler::new(
+                eyre::DefaultHandler::default_with(error),
+                DEFAULT_SETTINGS.clone(),
+            );
+            // The backtrace of the default handler of `eyre` is not
+            // accessible, so that another one is captured for records.
+            if backtrace_enabled() {
+                handler.backtrace = Some(Backtrace::new_unresolved());
+            }
+            handler
+        }
+    };
+    Box::new(handler)
+}
+
+/// Check whether backtraces are enabled by the `RUST_LIB_BACKTRACE` or
+/// `RUST_BACKTRACE` environment variables, like `color_eyre`.
+fn backtrace_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var("RUST_LIB_BACKTRACE")
+            .or_else(|_| std::env::var("RUST_BACKTRACE"))
+            .is_ok_and(|value| value != "0")
+    })
+}
+
+/// Install the handler of this crate, wrapping the hooks of `color_eyre`
+/// configured by `builder`.
+pub(crate) fn install(
+    builder: HookBuilder,
+    settings: HandlerSettings,
+    install_panic_hook: bool,
+) -> eyre::Result<()> {
+    let (panic_hook, eyre_hook) = builder
+        .display_location_section(false)
+        .display_env_section(false)
+        .try_into_hooks()?;
+    if !ensure_hook() {
+        return Err(eyre::InstallError.into());
+    }
+    *CONFIG.write().unwrap_or_else(PoisonError::into_inner) = Some(HookConfig {
+        inner: eyre_hook.into_eyre_hook(),
+        settings: Arc::new(settings),
+    });
+    let panic_hook = if install_panic_hook {
+        panic_hook.into_panic_hook()
+    } else {
+        std::panic::take_hook()
+    };
+    std::panic::set_hook(panic::wrap_panic_hook(panic_hook));
+    Ok(())
+}
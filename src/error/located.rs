@@ -0,0 +1,249 @@
+//! Lightweight located error, usable without `std`.
+
+use super::{ErrorCode, ErrorCodeRecord, ErrorExt};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Debug, Display};
+use core::panic::Location;
+
+/// Error message, or typed error which can be downcast.
+enum Inner {
+    Message(String),
+    Error(Box<dyn Error + Send + Sync + 'static>),
+}
+
+/// Error capturing the source code location raising it, which only requires
+/// `core` and `alloc`.
+///
+/// It is the `Report` of this crate without the `std` feature, so that
+/// `fail!`, `ensure!`, `OptionExt` and `BoolExt` can also be used in
+/// embedded and WASM targets. Like `Report`, it does not implement `Error`,
+/// so that any error can be converted to it by the `?` operator.
+///
+/// Locations follow the location policy, which is only selected by the cargo
+/// features `location-*` without the `std` feature.
+///
+/// ```
+/// use extlib::error::LocatedError;
+///
+/// let err = LocatedError::new("device not ready");
+/// assert_eq!(err.to_string(), "device not ready");
+/// if let Some(location) = err.location() {
+///     assert_eq!(location.file(), file!());
+/// }
+/// ```
+#[must_use]
+pub struct LocatedError {
+    inner: Inner,
+    location: Option<&'static Location<'static>>,
+    code: Option<ErrorCodeRecord>,
+    helps: Vec<String>,
+    cause: Option<Box<LocatedError>>,
+}
+
+impl LocatedError {
+    /// Create an error message located at the caller.
+    #[track_caller]
+    pub fn new(message: impl Display) -> Self {
+        LocatedError::with_inner(Inner::Message(message.to_string()))
+    }
+
+    /// Create an error from a typed error, which can be downcast from it,
+    /// located at the caller.
+    #[track_caller]
+    pub fn from_error<E>(error: E) -> Self
+    where
+        E: Error + Send + Sync + 'static,
+    {
+        LocatedError::with_inner(Inner::Error(Box::new(error)))
+    }
+
+    #[track_caller]
+    fn with_inner(inner: Inner) -> Self {
+        LocatedError {
+            inner,
+            location: Some(Location::caller()).filter(|_| captures_location()),
+            code: None,
+            helps: Vec::new(),
+            cause: None,
+        }
+    }
+
+    /// Wrap the error with a message located at the caller, keeping the error
+    /// as its cause.
+    #[track_caller]
+    pub fn wrap_err(self, message: impl Display) -> Self {
+        let mut error = LocatedError::new(message);
+        error.cause = Some(Box::new(self));
+        error
+    }
+
+    /// Located error wrapped by this error, if any.
+    pub fn cause(&self) -> Option<&LocatedError> {
+        self.cause.as_deref()
+    }
+
+    /// Source code location where the error was raised, unless disabled by
+    /// the location policy.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+
+    /// Error code attached to the error.
+    pub fn error_code(&self) -> Option<&dyn ErrorCode> {
+        self.code.as_ref().map(|code| code as &dyn ErrorCode)
+    }
+
+    /// Attach an error code to the error.
+    pub fn with_code(mut self, code: impl ErrorCode) -> Self {
+        self.code = Some(ErrorCodeRecord::new(&code));
+        self
+    }
+
+    /// Exit status of a process terminated by the error, derived from its
+    /// error code, or `1` by default.
+    pub fn exit_status(&self) -> i32 {
+        self.error_code().map_or(1, |code| code.exit_status())
+    }
+
+    /// Notes, warnings and suggestions attached to the error, rendered like
+    /// `Note: ...`.
+    pub fn help_sections(&self) -> &[String] {
+        &self.helps
+    }
+
+    /// Source of the typed error, if any.
+    pub fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.inner {
+            Inner::Message(_) => None,
+            Inner::Error(err) => err.source(),
+        }
+    }
+
+    /// Return the typed error if it is of type `E`.
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: Error + 'static,
+    {
+        match &self.inner {
+            Inner::Message(_) => None,
+            Inner::Error(err) => err.downcast_ref::<E>(),
+        }
+    }
+
+    /// Return the outermost error of type `E` in the chain of the typed
+    /// error, or else in the chains of the wrapped errors.
+    pub fn find_cause<E>(&self) -> Option<&E>
+    where
+        E: Error + 'static,
+    {
+        if let Inner::Error(err) = &self.inner {
+            let mut cause: Option<&(dyn Error + 'static)> = Some(err.as_ref());
+            while let Some(err) = cause {
+                if let Some(err) = err.downcast_ref::<E>() {
+                    return Some(err);
+                }
+                cause = err.source();
+            }
+        }
+        self.cause
+            .as_ref()
+            .and_then(|cause| cause.find_cause::<E>())
+    }
+}
+
+/// Check whether locations are captured by the location policy.
+#[cfg(feature = "std")]
+fn captures_location() -> bool {
+    super::LocationPolicy::current().captures()
+}
+
+/// Check whether locations are captured by the location policy, which is the
+/// default one of `LocationPolicy` without the `std` feature.
+#[cfg(not(feature = "std"))]
+fn captures_location() -> bool {
+    !cfg!(feature = "location-never")
+        && (cfg!(feature = "location-hidden")
+            || !cfg!(feature = "location-debug-only")
+            || cfg!(debug_assertions))
+}
+
+/// Check whether locations are displayed by the location policy.
+#[cfg(feature = "std")]
+fn displays_location() -> bool {
+    super::LocationPolicy::current().displays()
+}
+
+/// Check whether locations are displayed by the location policy, which is
+/// the default one of `LocationPolicy` without the `std` feature.
+#[cfg(not(feature = "std"))]
+fn displays_location() -> bool {
+    !cfg!(feature = "location-never")
+        && !cfg!(feature = "location-hidden")
+        && (!cfg!(feature = "location-debug-only") || cfg!(debug_assertions))
+}
+
+impl<E> From<E> for LocatedError
+where
+    E: Error + Send + Sync + 'static,
+{
+    #[track_caller]
+    fn from(error: E) -> Self {
+        LocatedError::from_error(error)
+    }
+}
+
+impl Display for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            Inner::Message(msg) => write!(f, "{msg}"),
+            Inner::Error(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Debug for LocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self}")?;
+        let mut cause = self.source();
+        while let Some(err) = cause {
+            write!(f, "\nCaused by: {err}")?;
+            cause = err.source();
+        }
+        if let Some(location) = self.location
+            && displays_location()
+        {
+            write!(f, "\nLocation: {location}")?;
+        }
+        if let Some(code) = &self.code {
+            write!(f, "\nCode: {code}")?;
+        }
+        for help in &self.helps {
+            write!(f, "\n{help}")?;
+        }
+        if let Some(cause) = &self.cause {
+            write!(f, "\n\nCaused by: {cause:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ErrorExt for LocatedError {
+    fn with_note(mut self, note: impl Display) -> Self {
+        self.helps.push(format!("Note: {note}"));
+        self
+    }
+
+    fn with_warning(mut self, warning: impl Display) -> Self {
+        self.helps.push(format!("Warning: {warning}"));
+        self
+    }
+
+    fn with_help(mut self, help: impl Display) -> Self {
+        self.helps.push(format!("Help: {help}"));
+        self
+    }
+}
@@ -0,0 +1,953 @@
+//! Wrapper library to provide `color_eyre::eyre` utilities.
+//!
+//! Without the default `std` feature, `Report` is the lightweight
+//! `LocatedError`, and only the macros raising errors, `OptionExt`, `BoolExt`
+//! and error codes are available.
+
+#[cfg(feature = "std")]
+use color_eyre::eyre::{self, eyre};
+use core::fmt::{Debug, Display};
+
+#[cfg(feature = "std")]
+mod catalog;
+mod code;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "std")]
+mod diagnostic;
+#[cfg(feature = "std")]
+mod errors;
+#[cfg(feature = "std")]
+mod exit;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "async")]
+mod future;
+#[cfg(feature = "std")]
+mod handler;
+mod located;
+#[cfg(feature = "std")]
+mod panic;
+#[cfg(feature = "std")]
+mod policy;
+#[doc(hidden)]
+pub mod private;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "std")]
+mod retry;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
+
+#[cfg(feature = "std")]
+pub use catalog::{Catalog, LocalizedMessage, Localizer, system_locale};
+pub use code::{ErrorCategory, ErrorCode, ErrorCodeRecord};
+#[cfg(feature = "std")]
+pub use config::{ColorMode, ErrorConfig};
+#[cfg(feature = "std")]
+pub use diagnostic::{Diagnostic, Label};
+#[cfg(feature = "std")]
+pub use errors::{Errors, try_all};
+#[cfg(feature = "std")]
+pub use exit::{run_main, run_main_with};
+#[cfg(feature = "derive")]
+pub use extlib_derive::ExtError;
+#[cfg(feature = "std")]
+pub use fingerprint::{ErrorAggregator, ErrorGroup, Fingerprint, message_template};
+#[cfg(feature = "async")]
+pub use future::{FutureExt, InContext, StreamExt};
+#[cfg(feature = "std")]
+pub use handler::{ContextFrame, Handler, HelpKind, HelpSection, SourceLocation};
+pub use located::LocatedError;
+#[cfg(feature = "std")]
+pub use panic::{Panic, catch_panic, panic_on_error};
+#[cfg(feature = "std")]
+pub use policy::{LOCATION_POLICY_ENV, LocationPolicy, set_location_policy};
+#[cfg(feature = "std")]
+pub use record::{BacktraceFrame, ErrorRecord};
+#[cfg(feature = "std")]
+pub use render::{HtmlRenderer, MarkdownRenderer, PlainTextRenderer, ReportRenderer};
+#[cfg(feature = "std")]
+pub use retry::{Backoff, RetryPolicy, is_transient, retry};
+#[cfg(feature = "tracing")]
+pub use trace::TraceExt;
+
+//-------------------------------------------------------------------------
+// Wrapper type
+//-------------------------------------------------------------------------
+
+/// Wrapper to eyre::Result type
+pub type Result<T, E = Report> = core::result::Result<T, E>;
+
+#[cfg(feature = "std")]
+pub type Report = color_eyre::eyre::Report;
+
+/// Located error of `no_std` targets.
+#[cfg(not(feature = "std"))]
+pub type Report = LocatedError;
+
+//-------------------------------------------------------------------------
+// Wrapper functions
+//-------------------------------------------------------------------------
+
+/// Helper function to create an error and capture the source code location raising it.
+///
+/// NOTE: `Location::caller()` needs to be called from a function, not directly
+/// from a macro, to be able to capture the source code location of the caller.
+#[cfg(feature = "std")]
+#[track_caller]
+pub fn create_error(error_msg: impl Display) -> Report {
+    let report = if handler::ensure_hook() {
+        eyre!(format!("{error_msg}"))
+    } else {
+        Report::new(handler::Annotated::new(error_msg.to_string()))
+    };
+    locate(report, SourceLocation::caller())
+}
+
+/// Helper function to create an error from a typed error value, which can be
+/// downcast from the report, and capture the source code location raising it.
+#[cfg(feature = "std")]
+#[track_caller]
+pub fn create_error_from<E>(error: E) -> Report
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    handler::ensure_hook();
+    locate(eyre::Report::new(error), SourceLocation::caller())
+}
+
+/// Convert an error to a report, after setting the hook of this crate so that
+/// the report can record metadata.
+#[cfg(feature = "std")]
+#[track_caller]
+pub(crate) fn to_report<E: Into<Report>>(error: E) -> Report {
+    handler::ensure_hook();
+    error.into()
+}
+
+/// Record the location raising a report, if captured by the policy in effect,
+/// and with the `tracing` feature, the current span trace.
+#[cfg(feature = "std")]
+fn locate(report: Report, location: SourceLocation) -> Report {
+    let captures = LocationPolicy::current().captures();
+    if !captures && !cfg!(feature = "tracing") {
+        return report;
+    }
+    handler::update_metadata(report, |metadata| {
+        if captures {
+            metadata.location = Some(location);
+        }
+        #[cfg(feature = "tracing")]
+        {
+            metadata.span_trace = handler::capture_span_trace();
+        }
+    })
+}
+
+/// Helper function to create an error with a message, keeping an existing
+/// error as its cause, and capture the source code location raising it.
+///
+/// If the cause is a report already located elsewhere, the caller's location
+/// is recorded as a context frame of the report.
+#[cfg(feature = "std")]
+#[track_caller]
+pub fn create_error_with_source<E>(source: E, error_msg: impl Display) -> Report
+where
+    E: Into<Report>,
+{
+    let report = add_context(to_report(source), Some(error_msg.to_string()));
+    #[cfg(feature = "tracing")]
+    let report = match report.span_trace() {
+        Some(_) => report,
+        None => handler::update_metadata(report, |metadata| {
+            metadata.span_trace = handler::capture_span_trace();
+        }),
+    };
+    report
+}
+
+/// Helper function to create an error and capture the source code location raising it.
+#[cfg(not(feature = "std"))]
+#[track_caller]
+pub fn create_error(error_msg: impl Display) -> Report {
+    LocatedError::new(error_msg)
+}
+
+/// Helper function to create an error from a typed error value, which can be
+/// downcast from the report, and capture the source code location raising it.
+#[cfg(not(feature = "std"))]
+#[track_caller]
+pub fn create_error_from<E>(error: E) -> Report
+where
+    E: core::error::Error + Send + Sync + 'static,
+{
+    LocatedError::from_error(error)
+}
+
+/// Helper function to create an error with a message, keeping an existing
+/// error as its cause, and capture the source code location raising it.
+#[cfg(not(feature = "std"))]
+#[track_caller]
+pub fn create_error_with_source<E>(source: E, error_msg: impl Display) -> Report
+where
+    E: Into<Report>,
+{
+    source.into().wrap_err(error_msg)
+}
+
+//-------------------------------------------------------------------------
+// New macros
+//-------------------------------------------------------------------------
+
+/// Create a report which captures the caller's source code location, without
+/// returning it, such as in `map_err(|e| err!("Invalid input: {e}"))`.
+///
+/// The arguments are the same as of `fail!`.
+#[macro_export]
+macro_rules! err {
+    ($($arg:tt)+) => {
+        $crate::__create_error!($($arg)+)
+    };
+}
+
+/// Create an error message which also captures caller's source code location.
+///
+/// Like `err!`, it evaluates to the report without returning it.
+///
+/// The message can be preceded by an error code, like `error!(code = c, msg)`,
+/// and followed by notes, warnings and suggestions, like
+/// `error!(msg; note = n, warning = w, help = h)`.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::__create_error!($($arg)+)
+    };
+}
+
+/// Create a report with a formatted message which captures the caller's
+/// source code location, and keeps an existing error as its cause.
+///
+/// ```
+/// use extlib::error::Result;
+/// use extlib::err_with;
+///
+/// fn read_port(text: &str) -> Result<u16> {
+///     text.parse()
+///         .map_err(|e| err_with!(e, "Invalid port: {text}"))
+/// }
+/// ```
+#[macro_export]
+macro_rules! err_with {
+    ($source:expr, $($arg:tt)+) => {
+        $crate::error::create_error_with_source(
+            $source,
+            $crate::error::private::format!($($arg)+),
+        )
+    };
+}
+
+/// Report an error and exit the current function immediately, similar to the
+/// `return` statement.
+///
+/// A single typed error, such as `fail!(MyError::NotFound)`, is kept as the
+/// error of the report and can be downcast from it. Its error code is also
+/// attached to the report if it implements `ErrorCode`.
+///
+/// The message can be preceded by an error code, like `fail!(code = c, msg)`,
+/// and by a source error kept as its cause, like
+/// `fail!(source = err, "Cannot open {}", path)`, and followed by notes,
+/// warnings and suggestions, like `fail!(msg; note = n, warning = w, help = h)`.
+#[macro_export]
+macro_rules! fail {
+    ($($arg:tt)+) => {
+        return Err($crate::__create_error!($($arg)+));
+    };
+}
+
+/// Create a report from the arguments of `error!` and `fail!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __create_error {
+    // Split the message from the trailing sections.
+    (@split [$($msg:tt)*] ; $($key:ident = $value:expr),+ $(,)?) => {{
+        let report = $crate::__create_error!(@message $($msg)*);
+        $(let report = $crate::__create_error!(@section report, $key, $value);)+
+        report
+    }};
+    (@split [$($msg:tt)*] $head:tt $($tail:tt)*) => {
+        $crate::__create_error!(@split [$($msg)* $head] $($tail)*)
+    };
+    (@split [$($msg:tt)*]) => {
+        $crate::__create_error!(@message $($msg)*)
+    };
+
+    // Attach a section.
+    (@section $report:ident, note, $value:expr) => {
+        $crate::error::ErrorExt::with_note($report, $value)
+    };
+    (@section $report:ident, warning, $value:expr) => {
+        $crate::error::ErrorExt::with_warning($report, $value)
+    };
+    (@section $report:ident, help, $value:expr) => {
+        $crate::error::ErrorExt::with_help($report, $value)
+    };
+    (@section $report:ident, $key:ident, $value:expr) => {
+        compile_error!(concat!(
+            "unknown section `",
+            stringify!($key),
+            "`, expected `note`, `warning` or `help`"
+        ))
+    };
+
+    // Create the report of the message.
+    (@message code = $code:expr $(,)?) => {
+        match $code {
+            code => $crate::error::private::with_code($crate::error::create_error(&code), code),
+        }
+    };
+    (@message code = $code:expr, $($arg:tt)+) => {
+        $crate::error::private::with_code($crate::__create_error!(@message $($arg)+), $code)
+    };
+    (@message source = $source:expr $(,)?) => {
+        $crate::__create_error!(@message $source)
+    };
+    (@message source = $source:expr, $($arg:tt)+) => {
+        $crate::error::create_error_with_source(
+            $source,
+            $crate::error::private::format!($($arg)+),
+        )
+    };
+    (@message $msg:literal $(,)?) => {
+        $crate::error::create_error($crate::error::private::format!($msg))
+    };
+    (@message $err:expr $(,)?) => {
+        match $err {
+            error => {
+                #[allow(unused_imports)]
+                use $crate::error::private::{CodedErrorKind, DisplayKind, ErrorKind};
+                (&&&$crate::error::private::Wrap(&error)).ext_kind().create(error)
+            }
+        }
+    };
+    (@message $fmt:expr, $($arg:tt)*) => {
+        $crate::error::create_error($crate::error::private::format!($fmt, $($arg)*))
+    };
+    (@ $($arg:tt)*) => {
+        compile_error!("invalid arguments of `error!` or `fail!`")
+    };
+
+    ($($arg:tt)+) => {
+        $crate::__create_error!(@split [] $($arg)+)
+    };
+}
+
+/// Report an error and exit the current function immediately if a condition
+/// is satisfied, the opposite of `ensure!`.
+///
+/// The arguments following the condition are the same as of `fail!`. In
+/// particular, `bail_if!(cond, source = err, msg)` keeps an existing error as
+/// the cause of the report, like `err_with!`.
+///
+/// ```
+/// use extlib::error::Result;
+/// use extlib::bail_if;
+///
+/// fn check_size(path: &str, size: u64) -> Result<()> {
+///     let limit: u64 = match std::env::var("SIZE_LIMIT") {
+///         Ok(text) => text.parse()?,
+///         Err(err) => {
+///             bail_if!(size > 1024, source = err, "{path} is too large");
+///             return Ok(());
+///         }
+///     };
+///     bail_if!(size > limit, "{path} is larger than {limit} bytes");
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! bail_if {
+    ($cond:expr $(,)?) => {
+        if $cond {
+            $crate::fail!(concat!("condition `", stringify!($cond), "` is true"));
+        }
+    };
+    ($cond:expr, source = $source:expr $(, $($arg:tt)+)?) => {
+        if $cond {
+            $crate::fail!(source = $source $(, $($arg)+)?);
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if $cond {
+            $crate::fail!($($arg)+);
+        }
+    };
+}
+
+/// Unwrap an option value, or report an error and exit the current function
+/// immediately if it is `None`.
+#[macro_export]
+macro_rules! expect_some {
+    ($opt:expr $(,)?) => {
+        match $opt {
+            Some(value) => value,
+            None => $crate::fail!(concat!("Expected `", stringify!($opt), "` to be `Some`")),
+        }
+    };
+    ($opt:expr, $($arg:tt)+) => {
+        match $opt {
+            Some(value) => value,
+            None => $crate::fail!($($arg)+),
+        }
+    };
+}
+
+/// Report an error and exit the current function immediately if a condition
+/// is not satisfied, similar to `assert!` but returning an error.
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::fail!(concat!("assertion `", stringify!($cond), "` failed"));
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::fail!($($arg)+);
+        }
+    };
+}
+
+/// Report an error and exit the current function immediately if two
+/// expressions are not equal, similar to `assert_eq!` but returning an error.
+///
+/// The arguments following the expressions are the same as of `fail!`. When
+/// they are given, the report has their message, and the failed assertion is
+/// attached as a note.
+///
+/// ```
+/// use extlib::error::Result;
+/// use extlib::ensure_eq;
+///
+/// fn check_header(magic: &[u8]) -> Result<()> {
+///     ensure_eq!(magic, b"EXT1", "Invalid header"; help = "Is it an EXT file?");
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! ensure_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left_val, right_val) => {
+                if !(*left_val == *right_val) {
+                    $crate::fail!(
+                        "assertion `{} == {}` failed\n  left: {:?}\n right: {:?}",
+                        stringify!($left),
+                        stringify!($right),
+                        left_val,
+                        right_val
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left_val, right_val) => {
+                if !(*left_val == *right_val) {
+                    return Err($crate::error::ErrorExt::with_note(
+                        $crate::__create_error!($($arg)+),
+                        $crate::error::private::format!(
+                            "assertion `{} == {}` failed\n  left: {:?}\n right: {:?}",
+                            stringify!($left),
+                            stringify!($right),
+                            left_val,
+                            right_val
+                        ),
+                    ));
+                }
+            }
+        }
+    };
+}
+
+/// Report an error and exit the current function immediately if two
+/// expressions are equal, similar to `assert_ne!` but returning an error.
+///
+/// The arguments following the expressions are the same as of `ensure_eq!`.
+#[macro_export]
+macro_rules! ensure_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left_val, right_val) => {
+                if *left_val == *right_val {
+                    $crate::fail!(
+                        "assertion `{} != {}` failed\n  left: {:?}\n right: {:?}",
+                        stringify!($left),
+                        stringify!($right),
+                        left_val,
+                        right_val
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left_val, right_val) => {
+                if *left_val == *right_val {
+                    return Err($crate::error::ErrorExt::with_note(
+                        $crate::__create_error!($($arg)+),
+                        $crate::error::private::format!(
+                            "assertion `{} != {}` failed\n  left: {:?}\n right: {:?}",
+                            stringify!($left),
+                            stringify!($right),
+                            left_val,
+                            right_val
+                        ),
+                    ));
+                }
+            }
+        }
+    };
+}
+
+/// Report an error and exit the current function immediately if an
+/// expression does not match a pattern, similar to `assert_matches!` but
+/// returning an error.
+///
+/// The arguments following the pattern are the same as of `ensure_eq!`.
+#[macro_export]
+macro_rules! ensure_matches {
+    ($left:expr, $pattern:pat $(if $guard:expr)? $(,)?) => {
+        match $left {
+            $pattern $(if $guard)? => {}
+            ref left_val => {
+                $crate::fail!(
+                    "assertion `{} matches {}` failed\n  left: {:?}",
+                    stringify!($left),
+                    stringify!($pattern $(if $guard)?),
+                    left_val
+                );
+            }
+        }
+    };
+    ($left:expr, $pattern:pat $(if $guard:expr)?, $($arg:tt)+) => {
+        match $left {
+            $pattern $(if $guard)? => {}
+            ref left_val => {
+                return Err($crate::error::ErrorExt::with_note(
+                    $crate::__create_error!($($arg)+),
+                    $crate::error::private::format!(
+                        "assertion `{} matches {}` failed\n  left: {:?}",
+                        stringify!($left),
+                        stringify!($pattern $(if $guard)?),
+                        left_val
+                    ),
+                ));
+            }
+        }
+    };
+}
+
+/// Return an error capturing the caller's source code location.
+#[track_caller]
+pub fn report_error<T>(error_msg: impl Display) -> Result<T> {
+    let report = create_error(error_msg);
+    Err(report)
+}
+
+//-------------------------------------------------------------------------
+// New utilities to handle option types of errors
+//-------------------------------------------------------------------------
+
+pub trait OptionExt<T> {
+    /// Transform `Some(v)` to `Ok(v)` and `None` to an error capturing the
+    /// caller's source code location.
+    fn ok_or_error<M>(self, message: M) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static;
+
+    /// Similar to `ok_or_error`, but the error message is lazily evaluated.
+    fn ok_or_else_error<M, F>(self, f: F) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+        F: FnOnce() -> M;
+}
+
+impl<T> OptionExt<T> for Option<T> {
+    #[track_caller]
+    fn ok_or_error<M>(self, message: M) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+    {
+        match self {
+            Some(ok) => Ok(ok),
+            None => Err(create_error(message)),
+        }
+    }
+
+    #[track_caller]
+    fn ok_or_else_error<M, F>(self, f: F) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+        F: FnOnce() -> M,
+    {
+        match self {
+            Some(ok) => Ok(ok),
+            None => Err(create_error(f())),
+        }
+    }
+}
+
+/// Trait to raise errors from guard conditions.
+pub trait BoolExt {
+    /// Return an error capturing the caller's source code location if the
+    /// condition is `true`, or `Ok(())` otherwise.
+    fn then_error<M>(self, message: M) -> Result<()>
+    where
+        M: Debug + Display + Send + Sync + 'static;
+
+    /// Similar to `then_error`, but the error message is lazily evaluated.
+    fn then_error_with<M, F>(self, f: F) -> Result<()>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+        F: FnOnce() -> M;
+}
+
+impl BoolExt for bool {
+    #[track_caller]
+    fn then_error<M>(self, message: M) -> Result<()>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+    {
+        if self {
+            Err(create_error(message))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[track_caller]
+    fn then_error_with<M, F>(self, f: F) -> Result<()>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+        F: FnOnce() -> M,
+    {
+        if self { Err(create_error(f())) } else { Ok(()) }
+    }
+}
+
+//-------------------------------------------------------------------------
+// New utilities to add context to result types of errors
+//-------------------------------------------------------------------------
+
+/// Trait to add context to errors, capturing the caller's source code
+/// location of every layer the error is propagated through.
+#[cfg(feature = "std")]
+pub trait ResultExt<T> {
+    /// Wrap the error with a context message.
+    fn context<M>(self, message: M) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static;
+
+    /// Wrap the error with a lazily evaluated context message.
+    fn with_context<M, F>(self, f: F) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+        F: FnOnce() -> M;
+
+    /// Record the caller's location without adding a context message.
+    fn context_here(self) -> Result<T>;
+}
+
+#[cfg(feature = "std")]
+impl<T, E> ResultExt<T> for Result<T, E>
+where
+    E: Into<Report>,
+{
+    #[track_caller]
+    fn context<M>(self, message: M) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+    {
+        match self {
+            Ok(ok) => Ok(ok),
+            Err(err) => Err(add_context(to_report(err), Some(message))),
+        }
+    }
+
+    #[track_caller]
+    fn with_context<M, F>(self, f: F) -> Result<T>
+    where
+        M: Debug + Display + Send + Sync + 'static,
+        F: FnOnce() -> M,
+    {
+        match self {
+            Ok(ok) => Ok(ok),
+            Err(err) => Err(add_context(to_report(err), Some(f()))),
+        }
+    }
+
+    #[track_caller]
+    fn context_here(self) -> Result<T> {
+        match self {
+            Ok(ok) => Ok(ok),
+            Err(err) => Err(add_context::<String>(to_report(err), None)),
+        }
+    }
+}
+
+/// Wrap a report with an optional message and push a context frame
+/// capturing the caller's location.
+///
+/// No frame is pushed if the report has just been raised at the same location.
+#[cfg(feature = "std")]
+#[track_caller]
+fn add_context<M>(report: Report, message: Option<M>) -> Report
+where
+    M: Debug + Display + Send + Sync + 'static,
+{
+    add_context_at(report, message, SourceLocation::caller())
+}
+
+/// Wrap a report with an optional message and push a context frame at a
+/// given location.
+#[cfg(feature = "std")]
+pub(crate) fn add_context_at<M>(
+    report: Report,
+    message: Option<M>,
+    location: SourceLocation,
+) -> Report
+where
+    M: Debug + Display + Send + Sync + 'static,
+{
+    let is_new = report.location() == Some(&location);
+    let frame = ContextFrame::new(message.as_ref().map(|msg| msg.to_string()), location);
+    let report = match message {
+        Some(msg) => report.wrap_err(msg),
+        None => report,
+    };
+    if !is_new && LocationPolicy::current().captures() {
+        handler::update_metadata(report, |metadata| metadata.contexts.push(frame))
+    } else {
+        report
+    }
+}
+
+//-------------------------------------------------------------------------
+// New utilities to query reports
+//-------------------------------------------------------------------------
+
+/// Trait to extend `Report` utilities.
+#[cfg(feature = "std")]
+pub trait ReportExt {
+    /// Source code location where the error was raised.
+    ///
+    /// The location is recorded whenever the `LocationPolicy` in effect
+    /// captures locations, whether or not `ErrorConfig::install` is called.
+    fn location(&self) -> Option<&SourceLocation>;
+
+    /// Context frames added while propagating the error, from the innermost
+    /// to the outermost.
+    fn contexts(&self) -> &[ContextFrame];
+
+    /// Notes, warnings and suggestions attached to the report.
+    fn help_sections(&self) -> &[HelpSection];
+
+    /// Error code attached to the report.
+    fn error_code(&self) -> Option<&dyn ErrorCode>;
+
+    /// Attach an error code to the report.
+    fn with_code(self, code: impl ErrorCode) -> Self;
+
+    /// Span trace captured when the error was raised.
+    ///
+    /// Span traces are only captured when a `tracing_error::ErrorLayer` is
+    /// part of the tracing subscriber in effect. They are always captured by
+    /// the macros raising errors, and otherwise when span trace capture is
+    /// enabled by `ErrorConfig::capture_span_trace` or the `RUST_SPANTRACE`
+    /// environment variable.
+    #[cfg(feature = "tracing")]
+    fn span_trace(&self) -> Option<&tracing_error::SpanTrace>;
+
+    /// Exit status of a process terminated by the report, derived from its
+    /// error code, or `1` by default.
+    fn exit_status(&self) -> i32 {
+        self.error_code().map_or(1, |code| code.exit_status())
+    }
+
+    /// Stable fingerprint of the report, to group similar errors.
+    fn fingerprint(&self) -> Fingerprint;
+
+    /// Return the root cause of the report if it is of type `E`.
+    fn root_cause_as<E>(&self) -> Option<&E>
+    where
+        E: std::error::Error + 'static;
+
+    /// Return the outermost error of type `E` in the chain of the report.
+    fn find_cause<E>(&self) -> Option<&E>
+    where
+        E: std::error::Error + 'static;
+
+    /// Check whether the chain of the report contains an error of type `E`.
+    fn has_cause<E>(&self) -> bool
+    where
+        E: std::error::Error + 'static,
+    {
+        self.find_cause::<E>().is_some()
+    }
+
+    /// Check whether the outermost `std::io::Error` in the chain of the report
+    /// is of the given kind.
+    fn is_io_kind(&self, kind: std::io::ErrorKind) -> bool {
+        self.find_cause::<std::io::Error>()
+            .is_some_and(|err| err.kind() == kind)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ReportExt for Report {
+    fn location(&self) -> Option<&SourceLocation> {
+        handler::metadata(self).and_then(|metadata| metadata.location.as_ref())
+    }
+
+    fn contexts(&self) -> &[ContextFrame] {
+        handler::metadata(self).map_or(&[], |metadata| &metadata.contexts)
+    }
+
+    fn help_sections(&self) -> &[HelpSection] {
+        handler::metadata(self).map_or(&[], |metadata| &metadata.helps)
+    }
+
+    fn error_code(&self) -> Option<&dyn ErrorCode> {
+        handler::metadata(self).and_then(|metadata| metadata.code.as_deref())
+    }
+
+    fn with_code(self, code: impl ErrorCode) -> Self {
+        handler::update_metadata(self, |metadata| metadata.code = Some(Box::new(code)))
+    }
+
+    #[cfg(feature = "tracing")]
+    fn span_trace(&self) -> Option<&tracing_error::SpanTrace> {
+        match self.handler().downcast_ref::<Handler>() {
+            Some(handler) => handler.span_trace(),
+            None => handler::metadata(self).and_then(|metadata| metadata.span_trace.as_ref()),
+        }
+    }
+
+    fn fingerprint(&self) -> Fingerprint {
+        Fingerprint::of(self)
+    }
+
+    fn root_cause_as<E>(&self) -> Option<&E>
+    where
+        E: std::error::Error + 'static,
+    {
+        handler::unannotated(self.root_cause()).downcast_ref::<E>()
+    }
+
+    fn find_cause<E>(&self) -> Option<&E>
+    where
+        E: std::error::Error + 'static,
+    {
+        self.chain()
+            .find_map(|err| handler::unannotated(err).downcast_ref::<E>())
+    }
+}
+
+/// Match the chain of a report against error types, evaluating the arm of the
+/// first type found in the chain, or the final `_` arm otherwise.
+///
+/// ```
+/// use extlib::error::Report;
+/// use extlib::match_error;
+///
+/// fn describe(report: &Report) -> String {
+///     match_error!(report, {
+///         err: std::io::Error => format!("I/O error: {}", err.kind()),
+///         err: std::num::ParseIntError => format!("invalid number: {err}"),
+///         _ => "unknown error".to_string(),
+///     })
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! match_error {
+    ($report:expr, { $($arms:tt)+ }) => {
+        match &$report {
+            report => $crate::match_error!(@arms report; $($arms)+),
+        }
+    };
+    (@arms $report:ident; _ => $body:expr $(,)?) => {
+        $body
+    };
+    (@arms $report:ident; $bind:ident : $ty:ty => $body:expr, $($rest:tt)+) => {
+        match {
+            use $crate::error::ReportExt as _;
+            $report.find_cause::<$ty>()
+        } {
+            Some($bind) => $body,
+            None => $crate::match_error!(@arms $report; $($rest)+),
+        }
+    };
+}
+
+//-------------------------------------------------------------------------
+// New utilities to attach help sections to reports
+//-------------------------------------------------------------------------
+
+/// Trait to attach notes, warnings and suggestions to reports.
+///
+/// Sections are always recorded and returned by `ReportExt::help_sections`,
+/// but are only rendered by the handler of this crate: if another `eyre` hook
+/// was set before it, such as by `color_eyre::install`, they are missing from
+/// rendered reports.
+pub trait ErrorExt {
+    /// Attach a note to the report.
+    fn with_note(self, note: impl Display) -> Self;
+
+    /// Attach a warning to the report.
+    fn with_warning(self, warning: impl Display) -> Self;
+
+    /// Attach a suggestion to the report.
+    fn with_help(self, help: impl Display) -> Self;
+}
+
+#[cfg(feature = "std")]
+impl ErrorExt for Report {
+    fn with_note(self, note: impl Display) -> Self {
+        add_help(self, HelpKind::Note, note)
+    }
+
+    fn with_warning(self, warning: impl Display) -> Self {
+        add_help(self, HelpKind::Warning, warning)
+    }
+
+    fn with_help(self, help: impl Display) -> Self {
+        add_help(self, HelpKind::Help, help)
+    }
+}
+
+/// Attach a help section to a report.
+#[cfg(feature = "std")]
+fn add_help(report: Report, kind: HelpKind, message: impl Display) -> Report {
+    let section = HelpSection::new(kind, message.to_string());
+    handler::update_metadata(report, |metadata| metadata.helps.push(section))
+}
+
+//-------------------------------------------------------------------------
+// Public utilities
+//-------------------------------------------------------------------------
+
+/// Configure new error reporting mechanism with the default settings.
+///
+/// Use `ErrorConfig` to customize the settings and handle installation errors.
+#[cfg(feature = "std")]
+pub fn config() {
+    let _ = ErrorConfig::new().install();
+}
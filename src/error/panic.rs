@@ -0,0 +1,130 @@
+//! Bridge between panics and reports.
+
+use super::{Handler, LocationPolicy, Report, Result, SourceLocation, handler};
+use core::fmt::{self, Display};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::panic::{PanicHookInfo, UnwindSafe};
+
+thread_local! {
+    /// Number of nested `catch_panic` calls running in the current thread.
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+
+    /// Location of the last panic of the current thread.
+    static PANIC_LOCATION: RefCell<Option<SourceLocation>> = const { RefCell::new(None) };
+}
+
+/// Type of panic hooks.
+pub(crate) type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+//-------------------------------------------------------------------------
+// Panic error
+//-------------------------------------------------------------------------
+
+/// Error converted from a panic caught by `catch_panic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panic {
+    message: String,
+    location: Option<SourceLocation>,
+}
+
+impl Panic {
+    /// Message of the panic.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Source code location of the panic.
+    ///
+    /// The location is only known when the panic hook of this crate is
+    /// installed by `ErrorConfig::install`.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+}
+
+impl Display for Panic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for Panic {}
+
+//-------------------------------------------------------------------------
+// Panic hook
+//-------------------------------------------------------------------------
+
+/// Wrap a panic hook to record the locations of panics, and to silence the
+/// panics caught by `catch_panic`.
+pub(crate) fn wrap_panic_hook(hook: PanicHook) -> PanicHook {
+    Box::new(move |info| {
+        let location = info
+            .location()
+            .map(|loc| SourceLocation::new(loc.file().to_string(), loc.line(), loc.column()));
+        PANIC_LOCATION.with(|last| *last.borrow_mut() = location);
+        if CATCHING.with(|catching| catching.get()) == 0 {
+            hook(info)
+        }
+    })
+}
+
+/// Extract the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+//-------------------------------------------------------------------------
+// Utilities
+//-------------------------------------------------------------------------
+
+/// Run a function and convert its panic, if any, to a report located where
+/// the panic occurred.
+///
+/// The report can be downcast to `Panic`.
+pub fn catch_panic<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    PANIC_LOCATION.with(|last| last.borrow_mut().take());
+    CATCHING.with(|catching| catching.set(catching.get() + 1));
+    let result = std::panic::catch_unwind(f);
+    CATCHING.with(|catching| catching.set(catching.get() - 1));
+
+    let payload = match result {
+        Ok(value) => return Ok(value),
+        Err(payload) => payload,
+    };
+    let location = PANIC_LOCATION.with(|last| last.borrow_mut().take());
+    let panic = Panic {
+        message: panic_message(payload.as_ref()),
+        location: location.clone(),
+    };
+    handler::ensure_hook();
+    let mut report = Report::new(panic);
+    if let Some(handler) = report.handler_mut().downcast_mut::<Handler>() {
+        handler.clear_location();
+    }
+    match location.filter(|_| LocationPolicy::current().captures()) {
+        Some(location) => Err(handler::update_metadata(report, |metadata| {
+            metadata.location = Some(location)
+        })),
+        None => Err(report),
+    }
+}
+
+/// Return the value of a result, or panic with the rendered report of its
+/// error, typically in tests.
+#[track_caller]
+pub fn panic_on_error<T>(result: Result<T>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(report) => panic!("{report:?}"),
+    }
+}
@@ -0,0 +1,236 @@
+//! Policy to capture and display source code locations raising errors.
+
+use core::fmt::{self, Display};
+use core::str::FromStr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Environment variable overriding the location policy, with the values
+/// `always`, `debug-only`, `never` or `hidden`.
+pub const LOCATION_POLICY_ENV: &str = "EXTLIB_ERROR_LOCATION";
+
+/// Location policy set at runtime, or `UNSET`.
+static POLICY: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Location policy read from the environment variable.
+static ENV_POLICY: OnceLock<Option<LocationPolicy>> = OnceLock::new();
+
+const UNSET: u8 = u8::MAX;
+
+//-------------------------------------------------------------------------
+// Location policy
+//-------------------------------------------------------------------------
+
+/// Policy to capture and display source code locations raising errors.
+///
+/// The policy in effect is, by order of precedence, the one given by the
+/// `EXTLIB_ERROR_LOCATION` environment variable, the one set at runtime by
+/// `set_location_policy` or `ErrorConfig::location_policy`, or the default one
+/// selected by the cargo features `location-never`, `location-hidden` and
+/// `location-debug-only`. Without these features, locations are always
+/// captured and displayed.
+///
+/// These features are not additive: cargo enables them for the whole program
+/// once any crate of the dependency graph enables them, so that a dependency
+/// enabling `location-never` disables the capture of all locations unless the
+/// policy is set at runtime. Libraries should therefore not enable them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LocationPolicy {
+    /// Capture and display locations.
+    Always,
+    /// Capture and display locations only in debug builds.
+    DebugOnly,
+    /// Never capture locations.
+    Never,
+    /// Capture locations, but do not display them in reports.
+    Hidden,
+}
+
+impl LocationPolicy {
+    /// Default policy selected by cargo features.
+    pub const DEFAULT: LocationPolicy = if cfg!(feature = "location-never") {
+        LocationPolicy::Never
+    } else if cfg!(feature = "location-hidden") {
+        LocationPolicy::Hidden
+    } else if cfg!(feature = "location-debug-only") {
+        LocationPolicy::DebugOnly
+    } else {
+        LocationPolicy::Always
+    };
+
+    /// Policy currently in effect.
+    pub fn current() -> LocationPolicy {
+        let env_policy = ENV_POLICY.get_or_init(|| {
+            std::env::var(LOCATION_POLICY_ENV)
+                .ok()
+                .and_then(|value| value.parse().ok())
+        });
+        let runtime_policy = match POLICY.load(Ordering::Relaxed) {
+            UNSET => None,
+            value => Some(LocationPolicy::from_u8(value)),
+        };
+        LocationPolicy::resolve(*env_policy, runtime_policy)
+    }
+
+    /// Resolve the policy in effect from the policies given by the
+    /// environment variable and set at runtime, if any.
+    fn resolve(
+        env_policy: Option<LocationPolicy>,
+        runtime_policy: Option<LocationPolicy>,
+    ) -> LocationPolicy {
+        env_policy
+            .or(runtime_policy)
+            .unwrap_or(LocationPolicy::DEFAULT)
+    }
+
+    /// Check whether locations are captured.
+    pub fn captures(&self) -> bool {
+        match self {
+            LocationPolicy::Always | LocationPolicy::Hidden => true,
+            LocationPolicy::DebugOnly => cfg!(debug_assertions),
+            LocationPolicy::Never => false,
+        }
+    }
+
+    /// Check whether locations are displayed in reports.
+    pub fn displays(&self) -> bool {
+        match self {
+            LocationPolicy::Always => true,
+            LocationPolicy::DebugOnly => cfg!(debug_assertions),
+            LocationPolicy::Never | LocationPolicy::Hidden => false,
+        }
+    }
+
+    fn from_u8(value: u8) -> LocationPolicy {
+        match value {
+            0 => LocationPolicy::Always,
+            1 => LocationPolicy::DebugOnly,
+            2 => LocationPolicy::Never,
+            _ => LocationPolicy::Hidden,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            LocationPolicy::Always => 0,
+            LocationPolicy::DebugOnly => 1,
+            LocationPolicy::Never => 2,
+            LocationPolicy::Hidden => 3,
+        }
+    }
+}
+
+impl Default for LocationPolicy {
+    fn default() -> Self {
+        LocationPolicy::DEFAULT
+    }
+}
+
+impl Display for LocationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LocationPolicy::Always => "always",
+            LocationPolicy::DebugOnly => "debug-only",
+            LocationPolicy::Never => "never",
+            LocationPolicy::Hidden => "hidden",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for LocationPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "always" => Ok(LocationPolicy::Always),
+            "debug" | "debug-only" => Ok(LocationPolicy::DebugOnly),
+            "never" => Ok(LocationPolicy::Never),
+            "hidden" => Ok(LocationPolicy::Hidden),
+            _ => Err(format!("Invalid location policy: {s}")),
+        }
+    }
+}
+
+/// Set the location policy at runtime.
+///
+/// The policy is still overridden by the `EXTLIB_ERROR_LOCATION` environment
+/// variable, if it is set.
+pub fn set_location_policy(policy: LocationPolicy) {
+    POLICY.store(policy.to_u8(), Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICIES: [LocationPolicy; 4] = [
+        LocationPolicy::Always,
+        LocationPolicy::DebugOnly,
+        LocationPolicy::Never,
+        LocationPolicy::Hidden,
+    ];
+
+    #[test]
+    fn parse_policies() {
+        for policy in POLICIES {
+            assert_eq!(policy.to_string().parse(), Ok(policy));
+        }
+        assert_eq!(" Debug\n".parse(), Ok(LocationPolicy::DebugOnly));
+        assert_eq!("NEVER".parse(), Ok(LocationPolicy::Never));
+        assert_eq!(
+            "sometimes".parse::<LocationPolicy>(),
+            Err("Invalid location policy: sometimes".to_string())
+        );
+        assert!("".parse::<LocationPolicy>().is_err());
+    }
+
+    #[test]
+    fn encode_policies() {
+        for policy in POLICIES {
+            assert_eq!(LocationPolicy::from_u8(policy.to_u8()), policy);
+            assert_ne!(policy.to_u8(), UNSET);
+        }
+    }
+
+    #[test]
+    fn environment_overrides_runtime_and_features() {
+        let hidden = Some(LocationPolicy::Hidden);
+        let never = Some(LocationPolicy::Never);
+        assert_eq!(
+            LocationPolicy::resolve(hidden, never),
+            LocationPolicy::Hidden
+        );
+        assert_eq!(
+            LocationPolicy::resolve(hidden, None),
+            LocationPolicy::Hidden
+        );
+        assert_eq!(LocationPolicy::resolve(None, never), LocationPolicy::Never);
+        assert_eq!(LocationPolicy::resolve(None, None), LocationPolicy::DEFAULT);
+    }
+
+    #[test]
+    #[cfg(not(any(
+        feature = "location-never",
+        feature = "location-hidden",
+        feature = "location-debug-only"
+    )))]
+    fn default_policy_without_features() {
+        assert_eq!(LocationPolicy::DEFAULT, LocationPolicy::Always);
+    }
+
+    #[test]
+    #[cfg(feature = "location-never")]
+    fn never_feature_takes_precedence() {
+        assert_eq!(LocationPolicy::DEFAULT, LocationPolicy::Never);
+    }
+
+    #[test]
+    fn capture_and_display_by_policy() {
+        assert!(LocationPolicy::Always.captures() && LocationPolicy::Always.displays());
+        assert!(LocationPolicy::Hidden.captures() && !LocationPolicy::Hidden.displays());
+        assert!(!LocationPolicy::Never.captures() && !LocationPolicy::Never.displays());
+        assert_eq!(LocationPolicy::DebugOnly.captures(), cfg!(debug_assertions));
+        assert_eq!(LocationPolicy::DebugOnly.displays(), cfg!(debug_assertions));
+    }
+}
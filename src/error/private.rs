@@ -0,0 +1,133 @@
+//! Private utilities used by the macros of this crate.
+//!
+//! The kind of a value given to `fail!` is selected at compile time by
+//! autoref-based specialization: typed errors with an error code, typed errors,
+//! and values which are only displayable.
+
+use super::{ErrorCode, ErrorCodeRecord, Report, create_error, create_error_from};
+use core::error::Error as StdError;
+use core::fmt::Display;
+
+pub use alloc::format;
+
+/// Attach an error code to a report.
+#[cfg(feature = "std")]
+pub fn with_code(report: Report, code: impl ErrorCode) -> Report {
+    super::ReportExt::with_code(report, code)
+}
+
+/// Attach an error code to a report.
+#[cfg(not(feature = "std"))]
+pub fn with_code(report: Report, code: impl ErrorCode) -> Report {
+    report.with_code(code)
+}
+
+/// Wrapper of a reference to a value given to `fail!`.
+pub struct Wrap<'a, T>(pub &'a T);
+
+//-------------------------------------------------------------------------
+// Typed errors with an error code
+//-------------------------------------------------------------------------
+
+pub struct CodedErrorTag;
+
+pub trait CodedErrorKind {
+    fn ext_kind(&self) -> CodedErrorTag {
+        CodedErrorTag
+    }
+}
+
+impl<E> CodedErrorKind for &&Wrap<'_, E> where E: StdError + ErrorCode {}
+
+impl CodedErrorTag {
+    #[track_caller]
+    pub fn create<E>(self, error: E) -> Report
+    where
+        E: StdError + ErrorCode,
+    {
+        let code = ErrorCodeRecord::new(&error);
+        with_code(create_error_from(error), code)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Typed errors
+//-------------------------------------------------------------------------
+
+pub struct ErrorTag;
+
+pub trait ErrorKind {
+    fn ext_kind(&self) -> ErrorTag {
+        ErrorTag
+    }
+}
+
+impl<E> ErrorKind for &Wrap<'_, E> where E: StdError + Send + Sync + 'static {}
+
+impl ErrorTag {
+    #[track_caller]
+    pub fn create<E>(self, error: E) -> Report
+    where
+        E: StdError + Send + Sync + 'static,
+    {
+        create_error_from(error)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Displayable values
+//-------------------------------------------------------------------------
+
+pub struct DisplayTag;
+
+pub trait DisplayKind {
+    fn ext_kind(&self) -> DisplayTag {
+        DisplayTag
+    }
+}
+
+impl<T> DisplayKind for Wrap<'_, T> where T: Display {}
+
+impl DisplayTag {
+    #[track_caller]
+    pub fn create(self, msg: impl Display) -> Report {
+        create_error(msg)
+    }
+}
+
+//-------------------------------------------------------------------------
+// Sources of derived errors
+//-------------------------------------------------------------------------
+
+/// Conversion of the sources of errors derived by `ExtError`, which can be
+/// typed errors or boxed errors, to error trait objects.
+pub trait AsDynError {
+    fn as_dyn_error(&self) -> &(dyn StdError + 'static);
+}
+
+impl<E> AsDynError for E
+where
+    E: StdError + 'static,
+{
+    fn as_dyn_error(&self) -> &(dyn StdError + 'static) {
+        self
+    }
+}
+
+impl AsDynError for dyn StdError + 'static {
+    fn as_dyn_error(&self) -> &(dyn StdError + 'static) {
+        self
+    }
+}
+
+impl AsDynError for dyn StdError + Send + 'static {
+    fn as_dyn_error(&self) -> &(dyn StdError + 'static) {
+        self
+    }
+}
+
+impl AsDynError for dyn StdError + Send + Sync + 'static {
+    fn as_dyn_error(&self) -> &(dyn StdError + 'static) {
+        self
+    }
+}
@@ -0,0 +1,124 @@
+//! Structured records of reports, to be serialized for structured logging.
+
+use super::{
+    ContextFrame, ErrorCode, ErrorCodeRecord, Handler, HelpSection, Report, ReportExt,
+    SourceLocation, handler,
+};
+
+#[cfg(feature = "serde")]
+use super::Result;
+
+//-------------------------------------------------------------------------
+// Backtrace frame
+//-------------------------------------------------------------------------
+
+/// Resolved frame of a captured backtrace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BacktraceFrame {
+    /// Demangled symbol name of the frame.
+    pub name: Option<String>,
+    /// Source file of the frame.
+    pub file: Option<String>,
+    /// Line number of the frame.
+    pub line: Option<u32>,
+}
+
+/// Collect the resolved frames of the backtrace captured by a report.
+fn backtrace_frames(report: &Report) -> Vec<BacktraceFrame> {
+    let Some(backtrace) = report
+        .handler()
+        .downcast_ref::<Handler>()
+        .and_then(|handler| handler.backtrace())
+    else {
+        return vec![];
+    };
+    let mut backtrace = backtrace.clone();
+    backtrace.resolve();
+    backtrace
+        .frames()
+        .iter()
+        .flat_map(|frame| frame.symbols())
+        .map(|symbol| BacktraceFrame {
+            name: symbol.name().map(|name| name.to_string()),
+            file: symbol.filename().map(|file| file.display().to_string()),
+            line: symbol.lineno(),
+        })
+        .collect()
+}
+
+//-------------------------------------------------------------------------
+// Error record
+//-------------------------------------------------------------------------
+
+/// Structured record of a report.
+///
+/// With the `serde` feature, records can be serialized to JSON by
+/// `ErrorRecord::to_json` and deserialized back by `ErrorRecord::from_json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorRecord {
+    /// Outermost error message.
+    pub message: String,
+    /// Messages of the underlying causes, from the outermost to the innermost.
+    pub causes: Vec<String>,
+    /// Source code location where the error was raised.
+    pub location: Option<SourceLocation>,
+    /// Context frames added while propagating the error.
+    pub contexts: Vec<ContextFrame>,
+    /// Error code attached to the report.
+    pub code: Option<ErrorCodeRecord>,
+    /// Notes, warnings and suggestions attached to the report.
+    pub help: Vec<HelpSection>,
+    /// Frames of the captured backtrace, if any.
+    pub backtrace: Vec<BacktraceFrame>,
+}
+
+impl ErrorRecord {
+    /// Record a report.
+    pub fn new(report: &Report) -> Self {
+        ErrorRecord {
+            message: report.to_string(),
+            causes: report.chain().skip(1).map(|err| err.to_string()).collect(),
+            location: report.location().cloned(),
+            contexts: report.contexts().to_vec(),
+            code: report.error_code().map(ErrorCodeRecord::new),
+            help: report.help_sections().to_vec(),
+            backtrace: backtrace_frames(report),
+        }
+    }
+
+    /// Rebuild a report from the record, for example to replay errors.
+    ///
+    /// The backtrace of the record is not restored.
+    pub fn into_report(self) -> Report {
+        let mut messages = std::iter::once(self.message).chain(self.causes).rev();
+        let innermost = messages.next().unwrap_or_default();
+        handler::ensure_hook();
+        let report = messages.fold(Report::msg(innermost), |report, msg| report.wrap_err(msg));
+        handler::update_metadata(report, |metadata| {
+            metadata.location = self.location;
+            metadata.contexts = self.contexts;
+            metadata.code = self.code.map(|code| Box::new(code) as Box<dyn ErrorCode>);
+            metadata.helps = self.help;
+        })
+    }
+
+    /// Serialize the record to JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Deserialize a record from JSON.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl From<&Report> for ErrorRecord {
+    fn from(report: &Report) -> Self {
+        ErrorRecord::new(report)
+    }
+}
@@ -0,0 +1,669 @@
+//! Rendering of reports to plain text, Markdown and HTML.
+
+use super::{ErrorRecord, LocationPolicy, Report};
+use core::fmt::{self, Write};
+
+//-------------------------------------------------------------------------
+// Renderer trait
+//-------------------------------------------------------------------------
+
+/// Renderer of reports to a text format, such as for issue trackers and
+/// dashboards.
+///
+/// Renderers write structured records of reports, so that records
+/// deserialized from logs can also be rendered.
+///
+/// ```
+/// use extlib::error::{MarkdownRenderer, ReportRenderer, Result};
+/// use extlib::fail;
+///
+/// fn load() -> Result<()> {
+///     fail!("Cannot load the config"; note = "The file is missing")
+/// }
+///
+/// let markdown = MarkdownRenderer::new().render(&load().unwrap_err());
+/// assert!(markdown.starts_with("### Error: Cannot load the config"));
+/// ```
+pub trait ReportRenderer {
+    /// Write the rendering of a record of a report.
+    fn write_record(&self, f: &mut dyn Write, record: &ErrorRecord) -> fmt::Result;
+
+    /// Render a record of a report.
+    fn render_record(&self, record: &ErrorRecord) -> String {
+        let mut output = String::new();
+        // Writing to a string never fails.
+        let _ = self.write_record(&mut output, record);
+        output
+    }
+
+    /// Render a report.
+    fn render(&self, report: &Report) -> String {
+        self.render_record(&ErrorRecord::new(report))
+    }
+}
+
+/// Check whether locations are displayed by the policy in effect.
+fn display_locations() -> bool {
+    LocationPolicy::current().displays()
+}
+
+/// Write the backtrace frames of a record, similar to `color_eyre`.
+fn write_backtrace(f: &mut dyn Write, record: &ErrorRecord) -> fmt::Result {
+    for (n, frame) in record.backtrace.iter().enumerate() {
+        let name = frame.name.as_deref().unwrap_or("<unknown>");
+        write!(f, "{n:>4}: {name}")?;
+        if let Some(file) = &frame.file {
+            write!(f, "\n        at {file}")?;
+            if let Some(line) = frame.line {
+                write!(f, ":{line}")?;
+            }
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+/// Split a message into its first line and the following lines, if any, such
+/// as the snippet of a `Diagnostic`.
+fn split_message(message: &str) -> (&str, Option<&str>) {
+    match message.split_once('\n') {
+        Some((first, rest)) => {
+            let rest = rest.trim_end_matches(['\r', '\n']);
+            (
+                first.trim_end_matches('\r'),
+                Some(rest).filter(|rest| !rest.is_empty()),
+            )
+        }
+        None => (message, None),
+    }
+}
+
+//-------------------------------------------------------------------------
+// Plain text
+//-------------------------------------------------------------------------
+
+/// Renderer of reports to plain text, without ANSI escape codes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainTextRenderer;
+
+impl PlainTextRenderer {
+    /// Create a plain text renderer.
+    pub fn new() -> Self {
+        PlainTextRenderer
+    }
+}
+
+impl ReportRenderer for PlainTextRenderer {
+    fn write_record(&self, f: &mut dyn Write, record: &ErrorRecord) -> fmt::Result {
+        write!(f, "Error: {}", record.message)?;
+        if !record.causes.is_empty() {
+            write!(f, "\n\nCaused by:")?;
+            for (n, cause) in record.causes.iter().enumerate() {
+                // Following lines of causes are aligned with their first line.
+                write!(f, "\n{n:>4}: {}", cause.replace('\n', "\n      "))?;
+            }
+        }
+        if let Some(code) = &record.code {
+            write!(f, "\n\nCode: {} ({})", code.code, code.category)?;
+        }
+        if display_locations() {
+            if let Some(location) = &record.location {
+                write!(f, "\n\nLocation: {location}")?;
+            }
+            if !record.contexts.is_empty() {
+                write!(f, "\n\nContext:")?;
+                for (n, frame) in record.contexts.iter().enumerate() {
+                    match frame.message() {
+                        Some(msg) => write!(f, "\n{n:>4}: {msg}\n      at {}", frame.location())?,
+                        None => write!(f, "\n{n:>4}: at {}", frame.location())?,
+                    }
+                }
+            }
+        }
+        if !record.help.is_empty() {
+            writeln!(f)?;
+            for section in &record.help {
+                write!(f, "\n{section}")?;
+            }
+        }
+        if !record.backtrace.is_empty() {
+            write!(f, "\n\nBacktrace:\n")?;
+            write_backtrace(f, record)?;
+        }
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------
+// Markdown
+//-------------------------------------------------------------------------
+
+/// Renderer of reports to Markdown, with the context and the backtrace in
+/// collapsible sections.
+#[derive(Debug, Clone, Copy)]
+pub struct MarkdownRenderer {
+    collapsible: bool,
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        MarkdownRenderer::new()
+    }
+}
+
+impl MarkdownRenderer {
+    /// Create a Markdown renderer with collapsible sections.
+    pub fn new() -> Self {
+        MarkdownRenderer { collapsible: true }
+    }
+
+    /// Render the context and the backtrace in collapsible `<details>`
+    /// sections, supported by most issue trackers.
+    pub fn collapsible(mut self, cond: bool) -> Self {
+        self.collapsible = cond;
+        self
+    }
+
+    /// Write the beginning of a section.
+    fn begin_section(&self, f: &mut dyn Write, title: &str) -> fmt::Result {
+        if self.collapsible {
+            write!(f, "\n\n<details>\n<summary>{title}</summary>\n\n")
+        } else {
+            write!(f, "\n\n**{title}:**\n\n")
+        }
+    }
+
+    /// Write the end of a section.
+    fn end_section(&self, f: &mut dyn Write) -> fmt::Result {
+        if self.collapsible {
+            write!(f, "\n</details>")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl ReportRenderer for MarkdownRenderer {
+    fn write_record(&self, f: &mut dyn Write, record: &ErrorRecord) -> fmt::Result {
+        let (title, details) = split_message(&record.message);
+        write!(f, "### Error: {}", escape_markdown(title))?;
+        if let Some(details) = details {
+            write!(f, "\n\n")?;
+            write_code_block(f, details, "")?;
+        }
+        if !record.causes.is_empty() {
+            write!(f, "\n\n**Caused by:**\n")?;
+            for (n, cause) in record.causes.iter().enumerate() {
+                let (first, details) = split_message(cause);
+                write!(f, "\n{}. {}", n + 1, escape_markdown(first))?;
+                if let Some(details) = details {
+                    write!(f, "\n\n")?;
+                    let indent = " ".repeat((n + 1).to_string().len() + 2);
+                    write_code_block(f, details, &indent)?;
+                }
+            }
+        }
+        if let Some(code) = &record.code {
+            write!(f, "\n\n**Code:** `{}` ({})", code.code, code.category)?;
+        }
+        if display_locations() {
+            if let Some(location) = &record.location {
+                write!(f, "\n\n**Location:** `{location}`")?;
+            }
+            if !record.contexts.is_empty() {
+                self.begin_section(f, "Context")?;
+                for (n, frame) in record.contexts.iter().enumerate() {
+                    write!(f, "{}. ", n + 1)?;
+                    if let Some(msg) = frame.message() {
+                        write!(f, "{} ", escape_markdown(msg))?;
+                    }
+                    writeln!(f, "at `{}`", frame.location())?;
+                }
+                self.end_section(f)?;
+            }
+        }
+        for section in &record.help {
+            write!(
+                f,
+                "\n\n> **{}:** {}",
+                section.kind(),
+                escape_markdown(section.message())
+            )?;
+        }
+        if !record.backtrace.is_empty() {
+            let title = format!("Backtrace ({} frames)", record.backtrace.len());
+            self.begin_section(f, &title)?;
+            writeln!(f, "```text")?;
+            write_backtrace(f, record)?;
+            writeln!(f, "```")?;
+            self.end_section(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write a text in a fenced code block, indented to be part of a list item.
+///
+/// The fence is longer than any sequence of backticks of the text, so that the
+/// text cannot close the block.
+fn write_code_block(f: &mut dyn Write, text: &str, indent: &str) -> fmt::Result {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    write!(f, "{indent}{fence}text")?;
+    for line in text.lines() {
+        write!(f, "\n{indent}{line}")?;
+    }
+    write!(f, "\n{indent}{fence}")
+}
+
+/// Escape the characters of a text interpreted by Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+//-------------------------------------------------------------------------
+// HTML
+//-------------------------------------------------------------------------
+
+/// Style of standalone HTML documents.
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+.error-report h1 { color: #b00020; font-size: 1.4em; }
+.error-report .help { border-left: 4px solid #888; padding-left: 0.5em; }
+.error-report pre { background: #f4f4f4; padding: 0.5em; overflow-x: auto; }";
+
+/// Renderer of reports to HTML, either as standalone documents or as
+/// fragments to embed in dashboards.
+#[derive(Debug, Clone)]
+pub struct HtmlRenderer {
+    standalone: bool,
+    title: String,
+}
+
+impl Default for HtmlRenderer {
+    fn default() -> Self {
+        HtmlRenderer::new()
+    }
+}
+
+impl HtmlRenderer {
+    /// Create a renderer of standalone HTML documents.
+    pub fn new() -> Self {
+        HtmlRenderer {
+            standalone: true,
+            title: "Error report".to_string(),
+        }
+    }
+
+    /// Render standalone documents, or `<div>` fragments otherwise.
+    pub fn standalone(mut self, cond: bool) -> Self {
+        self.standalone = cond;
+        self
+    }
+
+    /// Set the title of standalone documents.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Write the fragment of a record.
+    fn write_fragment(&self, f: &mut dyn Write, record: &ErrorRecord) -> fmt::Result {
+        writeln!(f, "<div class=\"error-report\">")?;
+        let (title, details) = split_message(&record.message);
+        writeln!(f, "<h1>Error: {}</h1>", escape_html(title))?;
+        if let Some(details) = details {
+            writeln!(f, "<pre>{}</pre>", escape_html(details))?;
+        }
+        if !record.causes.is_empty() {
+            writeln!(f, "<h2>Caused by</h2>\n<ol>")?;
+            for cause in &record.causes {
+                let (first, details) = split_message(cause);
+                write!(f, "<li>{}", escape_html(first))?;
+                if let Some(details) = details {
+                    write!(f, "<pre>{}</pre>", escape_html(details))?;
+                }
+                writeln!(f, "</li>")?;
+            }
+            writeln!(f, "</ol>")?;
+        }
+        let location = record.location.as_ref().filter(|_| display_locations());
+        if record.code.is_some() || location.is_some() {
+            writeln!(f, "<dl>")?;
+            if let Some(code) = &record.code {
+                writeln!(
+                    f,
+                    "<dt>Code</dt><dd><code>{}</code> ({})</dd>",
+                    escape_html(&code.code),
+                    code.category
+                )?;
+            }
+            if let Some(location) = location {
+                let location = escape_html(&location.to_string());
+                writeln!(f, "<dt>Location</dt><dd><code>{location}</code></dd>")?;
+            }
+            writeln!(f, "</dl>")?;
+        }
+        if !record.contexts.is_empty() && display_locations() {
+            writeln!(f, "<details>\n<summary>Context</summary>\n<ol>")?;
+            for frame in &record.contexts {
+                write!(f, "<li>")?;
+                if let Some(msg) = frame.message() {
+                    write!(f, "{} ", escape_html(msg))?;
+                }
+                let location = escape_html(&frame.location().to_string());
+                writeln!(f, "at <code>{location}</code></li>")?;
+            }
+            writeln!(f, "</ol>\n</details>")?;
+        }
+        for section in &record.help {
+            writeln!(
+                f,
+                "<p class=\"help\"><strong>{}:</strong> {}</p>",
+                section.kind(),
+                escape_html(section.message())
+            )?;
+        }
+        if !record.backtrace.is_empty() {
+            let mut backtrace = String::new();
+            write_backtrace(&mut backtrace, record)?;
+            writeln!(
+                f,
+                "<details>\n<summary>Backtrace ({} frames)</summary>",
+                record.backtrace.len()
+            )?;
+            writeln!(f, "<pre>{}</pre>\n</details>", escape_html(&backtrace))?;
+        }
+        writeln!(f, "</div>")
+    }
+}
+
+impl ReportRenderer for HtmlRenderer {
+    fn write_record(&self, f: &mut dyn Write, record: &ErrorRecord) -> fmt::Result {
+        if !self.standalone {
+            return self.write_fragment(f, record);
+        }
+        writeln!(f, "<!DOCTYPE html>\n<html>\n<head>")?;
+        writeln!(f, "<meta charset=\"utf-8\">")?;
+        writeln!(f, "<title>{}</title>", escape_html(&self.title))?;
+        writeln!(f, "<style>\n{HTML_STYLE}\n</style>")?;
+        writeln!(f, "</head>\n<body>")?;
+        self.write_fragment(f, record)?;
+        writeln!(f, "</body>\n</html>")
+    }
+}
+
+/// Escape the characters of a text interpreted by HTML.
+fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{
+        BacktraceFrame, ContextFrame, Diagnostic, ErrorCategory, ErrorCodeRecord, HelpKind,
+        HelpSection, SourceLocation,
+    };
+
+    fn record(message: &str) -> ErrorRecord {
+        ErrorRecord {
+            message: message.to_string(),
+            causes: vec![],
+            location: None,
+            contexts: vec![],
+            code: None,
+            help: vec![],
+            backtrace: vec![],
+        }
+    }
+
+    fn diagnostic_record() -> ErrorRecord {
+        let diag = Diagnostic::new("unexpected token", "input.txt", "let x = ;\n")
+            .with_label(8..9, "expected expression");
+        record(&diag.to_string())
+    }
+
+    fn full_record() -> ErrorRecord {
+        ErrorRecord {
+            causes: vec!["No such file <app.toml>".to_string()],
+            code: Some(ErrorCodeRecord {
+                code: "C001".to_string(),
+                category: ErrorCategory::NotFound,
+                exit_status: 66,
+                http_status: 404,
+            }),
+            help: vec![HelpSection::new(
+                HelpKind::Help,
+                "Create `app.toml`".to_string(),
+            )],
+            backtrace: vec![BacktraceFrame {
+                name: Some("app::main".to_string()),
+                file: Some("src/main.rs".to_string()),
+                line: Some(3),
+            }],
+            ..record("Cannot load the config")
+        }
+    }
+
+    #[test]
+    fn split_messages() {
+        assert_eq!(split_message("boom"), ("boom", None));
+        assert_eq!(split_message("boom\r\n"), ("boom", None));
+        assert_eq!(
+            split_message("boom\n  a\n  b\n"),
+            ("boom", Some("  a\n  b"))
+        );
+    }
+
+    #[test]
+    fn render_plain_text() {
+        assert_eq!(
+            PlainTextRenderer::new().render_record(&full_record()),
+            "Error: Cannot load the config\n\n\
+             Caused by:\n   \
+             0: No such file <app.toml>\n\n\
+             Code: C001 (not found)\n\n\
+             Help: Create `app.toml`\n\n\
+             Backtrace:\n   \
+             0: app::main\n        \
+             at src/main.rs:3\n"
+        );
+    }
+
+    #[test]
+    fn render_plain_text_multi_line_messages() {
+        let mut record = diagnostic_record();
+        record.causes = vec!["first\nsecond".to_string()];
+        assert_eq!(
+            PlainTextRenderer::new().render_record(&record),
+            "Error: unexpected token\n \
+             --> input.txt:1:9\n  \
+             |\n\
+             1 | let x = ;\n  \
+             |         ^ expected expression\n\n\
+             Caused by:\n   \
+             0: first\n      \
+             second"
+        );
+    }
+
+    #[test]
+    fn render_locations_and_contexts_by_policy() {
+        let location = SourceLocation::new("src/config.rs", 12, 5);
+        let record = ErrorRecord {
+            location: Some(location.clone()),
+            contexts: vec![ContextFrame::new(
+                Some("Cannot start".to_string()),
+                SourceLocation::new("src/main.rs", 3, 9),
+            )],
+            ..record("boom")
+        };
+        let displays = LocationPolicy::current().displays();
+        let plain = PlainTextRenderer::new().render_record(&record);
+        assert_eq!(plain.contains("Location: src/config.rs:12:5"), displays);
+        assert_eq!(
+            plain.contains("   0: Cannot start\n      at src/main.rs:3:9"),
+            displays
+        );
+        let markdown = MarkdownRenderer::new().render_record(&record);
+        assert_eq!(
+            markdown.contains("**Location:** `src/config.rs:12:5`"),
+            displays
+        );
+        let html = HtmlRenderer::new().render_record(&record);
+        assert_eq!(
+            html.contains("<dd><code>src/config.rs:12:5</code></dd>"),
+            displays
+        );
+    }
+
+    #[test]
+    fn render_markdown() {
+        assert_eq!(
+            MarkdownRenderer::new()
+                .collapsible(false)
+                .render_record(&full_record()),
+            "### Error: Cannot load the config\n\n\
+             **Caused by:**\n\n\
+             1. No such file \\<app.toml\\>\n\n\
+             **Code:** `C001` (not found)\n\n\
+             > **Help:** Create \\`app.toml\\`\n\n\
+             **Backtrace (1 frames):**\n\n\
+             ```text\n   \
+             0: app::main\n        \
+             at src/main.rs:3\n\
+             ```\n"
+        );
+    }
+
+    #[test]
+    fn render_markdown_collapsible_sections() {
+        let markdown = MarkdownRenderer::new().render_record(&full_record());
+        assert!(
+            markdown.ends_with(
+                "<details>\n<summary>Backtrace (1 frames)</summary>\n\n\
+                 ```text\n   0: app::main\n        at src/main.rs:3\n```\n\n</details>"
+            ),
+            "{markdown}"
+        );
+    }
+
+    #[test]
+    fn render_markdown_escapes_messages() {
+        let markdown = MarkdownRenderer::new().render_record(&record("# [a](b) *c* _d_ |e| \\"));
+        assert_eq!(
+            markdown,
+            "### Error: \\# \\[a\\](b) \\*c\\* \\_d\\_ \\|e\\| \\\\"
+        );
+    }
+
+    #[test]
+    fn render_markdown_multi_line_messages() {
+        let mut record = diagnostic_record();
+        record.causes = vec!["first\n```\nsecond".to_string()];
+        assert_eq!(
+            MarkdownRenderer::new().render_record(&record),
+            "### Error: unexpected token\n\n\
+             ```text\n \
+             --> input.txt:1:9\n  \
+             |\n\
+             1 | let x = ;\n  \
+             |         ^ expected expression\n\
+             ```\n\n\
+             **Caused by:**\n\n\
+             1. first\n\n   \
+             ````text\n   \
+             ```\n   \
+             second\n   \
+             ````"
+        );
+    }
+
+    #[test]
+    fn render_html_fragment() {
+        assert_eq!(
+            HtmlRenderer::new()
+                .standalone(false)
+                .render_record(&full_record()),
+            "<div class=\"error-report\">\n\
+             <h1>Error: Cannot load the config</h1>\n\
+             <h2>Caused by</h2>\n\
+             <ol>\n\
+             <li>No such file &lt;app.toml&gt;</li>\n\
+             </ol>\n\
+             <dl>\n\
+             <dt>Code</dt><dd><code>C001</code> (not found)</dd>\n\
+             </dl>\n\
+             <p class=\"help\"><strong>Help:</strong> Create `app.toml`</p>\n\
+             <details>\n\
+             <summary>Backtrace (1 frames)</summary>\n\
+             <pre>   0: app::main\n        at src/main.rs:3\n</pre>\n\
+             </details>\n\
+             </div>\n"
+        );
+    }
+
+    #[test]
+    fn render_html_document() {
+        let html = HtmlRenderer::new()
+            .title("Report <1>")
+            .render_record(&record("boom"));
+        assert!(html.starts_with("<!DOCTYPE html>\n"), "{html}");
+        assert!(html.contains("<title>Report &lt;1&gt;</title>"), "{html}");
+        assert!(html.ends_with("</div>\n</body>\n</html>\n"), "{html}");
+    }
+
+    #[test]
+    fn render_html_escapes_messages() {
+        let html = HtmlRenderer::new()
+            .standalone(false)
+            .render_record(&record("<script>\"a\" & 'b'</script>"));
+        assert!(
+            html.contains(
+                "<h1>Error: &lt;script&gt;&quot;a&quot; &amp; &#39;b&#39;&lt;/script&gt;</h1>"
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn render_html_multi_line_messages() {
+        let mut record = diagnostic_record();
+        record.causes = vec!["first\nsecond <b>".to_string()];
+        assert_eq!(
+            HtmlRenderer::new().standalone(false).render_record(&record),
+            "<div class=\"error-report\">\n\
+             <h1>Error: unexpected token</h1>\n\
+             <pre> --&gt; input.txt:1:9\n  \
+             |\n\
+             1 | let x = ;\n  \
+             |         ^ expected expression</pre>\n\
+             <h2>Caused by</h2>\n\
+             <ol>\n\
+             <li>first<pre>second &lt;b&gt;</pre></li>\n\
+             </ol>\n\
+             </div>\n"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Retry of fallible operations keyed on the classification of their errors.
+
+use super::{ErrorCategory, ErrorExt, Report, ReportExt, Result};
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Classifier deciding whether an error should be retried.
+type Classifier = Arc<dyn Fn(&Report) -> bool + Send + Sync>;
+
+//-------------------------------------------------------------------------
+// Backoff
+//-------------------------------------------------------------------------
+
+/// Strategy to compute delays between attempts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// Wait the same delay between all attempts.
+    Fixed(Duration),
+    /// Multiply the delay by `factor` after every attempt, up to `max`.
+    ///
+    /// Factors below 1 or not finite keep the initial delay.
+    Exponential {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    },
+    /// Similar to `Exponential`, but wait a random delay between zero and the
+    /// computed delay, to spread the attempts of concurrent clients.
+    Jittered {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    /// Delay to wait after a failed attempt, numbered from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential {
+                initial,
+                factor,
+                max,
+            } => exponential_delay(initial, factor, max, attempt),
+            Backoff::Jittered {
+                initial,
+                factor,
+                max,
+            } => exponential_delay(initial, factor, max, attempt).mul_f64(random_fraction()),
+        }
+    }
+}
+
+/// Compute the delay of an exponential backoff after an attempt.
+fn exponential_delay(initial: Duration, factor: f64, max: Duration, attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+    // Factors below 1, including negative ones, and non-finite factors would
+    // give decreasing, alternating or invalid delays, so the delay is fixed.
+    let factor = if factor.is_finite() && factor >= 1.0 {
+        factor
+    } else {
+        1.0
+    };
+    let secs = initial.as_secs_f64() * factor.powi(exponent);
+    if secs.is_finite() && secs < max.as_secs_f64() {
+        Duration::from_secs_f64(secs)
+    } else {
+        max
+    }
+}
+
+/// Generate a pseudo-random number in `[0, 1)`, which is good enough to jitter
+/// delays without depending on a random number generator.
+fn random_fraction() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos() as u64);
+    let mut x = nanos ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+//-------------------------------------------------------------------------
+// Retry policy
+//-------------------------------------------------------------------------
+
+/// Policy to retry fallible operations.
+///
+/// By default, only transient errors are retried, as classified by
+/// `is_transient`.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    backoff: Backoff,
+    classifier: Classifier,
+}
+
+impl RetryPolicy {
+    /// Create a policy with a given backoff and 3 attempts at most.
+    pub fn new(backoff: Backoff) -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff,
+            classifier: Arc::new(is_transient),
+        }
+    }
+
+    /// Create a policy waiting a fixed delay between attempts.
+    pub fn fixed(delay: Duration) -> Self {
+        RetryPolicy::new(Backoff::Fixed(delay))
+    }
+
+    /// Create a policy doubling the delay after every attempt, up to 30s.
+    pub fn exponential(initial: Duration) -> Self {
+        RetryPolicy::new(Backoff::Exponential {
+            initial,
+            factor: 2.0,
+            max: Duration::from_secs(30),
+        })
+    }
+
+    /// Create a policy waiting random delays bounded by a doubling delay,
+    /// up to 30s.
+    pub fn jittered(initial: Duration) -> Self {
+        RetryPolicy::new(Backoff::Jittered {
+            initial,
+            factor: 2.0,
+            max: Duration::from_secs(30),
+        })
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the classifier deciding whether an error should be retried.
+    pub fn retry_if<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&Report) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+
+    /// Backoff of the policy.
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Check whether an error should be retried.
+    pub fn should_retry(&self, report: &Report) -> bool {
+        (self.classifier)(report)
+    }
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::exponential(Duration::from_millis(100))
+    }
+}
+
+//-------------------------------------------------------------------------
+// Utilities
+//-------------------------------------------------------------------------
+
+/// Check whether an error is transient: its error code is of the category
+/// `ErrorCategory::Unavailable`, or its chain contains an I/O error of a kind
+/// such as `TimedOut` or `ConnectionRefused`.
+pub fn is_transient(report: &Report) -> bool {
+    if let Some(code) = report.error_code() {
+        return code.category() == ErrorCategory::Unavailable;
+    }
+    report.find_cause::<std::io::Error>().is_some_and(|err| {
+        matches!(
+            err.kind(),
+            ErrorKind::TimedOut
+                | ErrorKind::Interrupted
+                | ErrorKind::WouldBlock
+                | ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::BrokenPipe
+                | ErrorKind::HostUnreachable
+                | ErrorKind::NetworkUnreachable
+                | ErrorKind::ResourceBusy
+        )
+    })
+}
+
+/// Run an operation until it succeeds, its error should not be retried, or
+/// the maximum number of attempts is reached.
+///
+/// The errors of the previous attempts are attached as notes to the returned
+/// report.
+///
+/// ```no_run
+/// use extlib::error::{RetryPolicy, retry};
+/// use std::net::TcpStream;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::exponential(Duration::from_millis(50)).max_attempts(5);
+/// let stream = retry(&policy, || Ok(TcpStream::connect("127.0.0.1:8080")?));
+/// ```
+pub fn retry<T, F>(policy: &RetryPolicy, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    let mut failures: Vec<String> = vec![];
+    let mut attempt = 1;
+    loop {
+        let report = match operation() {
+            Ok(value) => return Ok(value),
+            Err(report) => report,
+        };
+        if attempt >= policy.max_attempts || !policy.should_retry(&report) {
+            let report = failures
+                .into_iter()
+                .enumerate()
+                .fold(report, |report, (n, failure)| {
+                    report.with_note(format!("Attempt {} failed: {failure}", n + 1))
+                });
+            return Err(report.with_note(format!("Gave up after {attempt} attempt(s)")));
+        }
+        failures.push(format!("{report:#}"));
+        std::thread::sleep(policy.backoff.delay(attempt));
+        attempt += 1;
+    }
+}
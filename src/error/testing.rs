@@ -0,0 +1,385 @@
+//! Utilities to test rendered error reports.
+//!
+//! Rendered reports contain color codes, absolute paths and line numbers
+//! which make them unstable across machines and edits. `Normalizer` renders
+//! reports in a plain form suitable for assertions and snapshots.
+//!
+//! Locations, contexts and notes are recorded even if the handler of this
+//! crate is not installed, but its rendering is only used once it is, for
+//! example by `testing::install` at the beginning of each test.
+//!
+//! ```
+//! use extlib::error::{Result, testing};
+//! use extlib::{assert_error_contains, assert_fails_with, fail};
+//!
+//! fn parse(port: &str) -> Result<u16> {
+//!     match port.parse() {
+//!         Ok(port) => Ok(port),
+//!         Err(_) => fail!("Invalid port: {port}"; help = "Use a number"),
+//!     }
+//! }
+//!
+//! testing::install();
+//! assert_fails_with!(parse("http"), "Invalid port: http");
+//! assert_error_contains!(parse("http"), "Help: Use a number");
+//! ```
+
+use super::{ColorMode, ErrorConfig, Report, to_report};
+
+/// Prefixes of the hints about environment variables, which are removed.
+const ENV_HINTS: [&str; 3] = [
+    "Backtrace omitted.",
+    "Run with RUST_BACKTRACE=",
+    "Run with COLORBT_SHOW_HIDDEN=",
+];
+
+//-------------------------------------------------------------------------
+// Normalizer
+//-------------------------------------------------------------------------
+
+/// Renderer of reports in a normalized plain form.
+///
+/// The rendering removes ANSI escape codes, hints about environment
+/// variables and trailing whitespaces, replaces the backslashes of Windows
+/// paths by slashes, and relativizes paths to the current directory and to
+/// the `CARGO_MANIFEST_DIR` directory. Line and column numbers of locations
+/// can also be masked as `LL:CC`.
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer {
+    mask_line_numbers: bool,
+    base_dirs: Vec<String>,
+}
+
+impl Normalizer {
+    /// Create a normalizer relativizing paths to the current directory and
+    /// to the `CARGO_MANIFEST_DIR` directory.
+    pub fn new() -> Self {
+        let current_dir = std::env::current_dir()
+            .ok()
+            .map(|dir| dir.display().to_string());
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").ok();
+        Normalizer {
+            mask_line_numbers: false,
+            base_dirs: manifest_dir.into_iter().chain(current_dir).collect(),
+        }
+    }
+
+    /// Mask line and column numbers of locations, like `src/main.rs:LL:CC`.
+    pub fn mask_line_numbers(mut self, cond: bool) -> Self {
+        self.mask_line_numbers = cond;
+        self
+    }
+
+    /// Relativize paths to an additional base directory.
+    pub fn base_dir(mut self, dir: impl Into<String>) -> Self {
+        self.base_dirs.push(dir.into());
+        self
+    }
+
+    /// Render a report in a normalized plain form.
+    pub fn render(&self, report: &Report) -> String {
+        self.normalize(&format!("{report:?}"))
+    }
+
+    /// Normalize a rendered report.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut base_dirs: Vec<String> = self
+            .base_dirs
+            .iter()
+            .map(|dir| format!("{}/", dir.replace('\\', "/").trim_end_matches('/')))
+            .collect();
+        let mut text = normalize_separators(&strip_ansi(text), &base_dirs);
+        base_dirs.sort_by_key(|dir| std::cmp::Reverse(dir.len()));
+        for dir in base_dirs {
+            text = text.replace(&dir, "");
+        }
+        if self.mask_line_numbers {
+            text = mask_line_numbers(&text);
+        }
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !ENV_HINTS.iter().any(|hint| line.starts_with(hint)))
+            .collect();
+        lines.join("\n").trim_matches('\n').to_string()
+    }
+}
+
+/// Render a report in a normalized plain form, keeping line numbers.
+pub fn render_plain(report: &Report) -> String {
+    Normalizer::new().render(report)
+}
+
+/// Render a report in a normalized plain form, masking line numbers.
+pub fn render_masked(report: &Report) -> String {
+    Normalizer::new().mask_line_numbers(true).render(report)
+}
+
+/// Install the error handler for tests, without colors or panic hook.
+///
+/// Installing the handler more than once has no effect, so it can be called
+/// at the beginning of every test.
+pub fn install() {
+    let _ = ErrorConfig::new()
+        .color_mode(ColorMode::Never)
+        .panic_hook(false)
+        .install();
+}
+
+/// Return the error of a result, or panic if it is a value.
+#[doc(hidden)]
+#[track_caller]
+pub fn expect_report<T, E>(result: Result<T, E>) -> Report
+where
+    E: Into<Report>,
+{
+    match result {
+        Ok(_) => panic!("expected an error, but the result is a value"),
+        Err(err) => to_report(err),
+    }
+}
+
+//-------------------------------------------------------------------------
+// Text utilities
+//-------------------------------------------------------------------------
+
+/// Remove ANSI escape sequences from a text.
+fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            result.push(c);
+            continue;
+        }
+        if chars.next_if_eq(&'[').is_some() {
+            // Control sequence: parameters and intermediates up to a final
+            // byte in the range `@` to `~`.
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        } else {
+            chars.next();
+        }
+    }
+    result
+}
+
+/// Replace the backslashes of paths by slashes in a text, keeping the other
+/// backslashes, such as the ones of messages quoting regexes.
+///
+/// Paths are the words containing a location like `.rs:12`, a Windows drive
+/// like `C:\`, or one of the base directories.
+fn normalize_separators(text: &str, base_dirs: &[String]) -> String {
+    let is_path = |word: &str| {
+        let normalized = word.replace('\\', "/");
+        normalized.contains(".rs:")
+            || word
+                .as_bytes()
+                .windows(3)
+                .any(|w| w[0].is_ascii_alphabetic() && w[1] == b':' && w[2] == b'\\')
+            || base_dirs
+                .iter()
+                .any(|dir| normalized.contains(dir.as_str()))
+    };
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let word_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, after) = rest.split_at(word_len);
+        if word.contains('\\') && is_path(word) {
+            result.push_str(&word.replace('\\', "/"));
+        } else {
+            result.push_str(word);
+        }
+        let space_len = after
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(after.len());
+        result.push_str(&after[..space_len]);
+        rest = &after[space_len..];
+    }
+    result
+}
+
+/// Mask the line and column numbers following `.rs:` in a text.
+fn mask_line_numbers(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find(".rs:") {
+        result.push_str(&rest[..idx + 4]);
+        rest = &rest[idx + 4..];
+        let line_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if line_len == 0 {
+            continue;
+        }
+        result.push_str("LL");
+        rest = &rest[line_len..];
+        if let Some(after) = rest.strip_prefix(':') {
+            let column_len = after
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after.len());
+            if column_len > 0 {
+                result.push_str(":CC");
+                rest = &after[column_len..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+//-------------------------------------------------------------------------
+// Assertion macros
+//-------------------------------------------------------------------------
+
+/// Assert that a result is an error whose normalized rendering, as of
+/// `testing::render_plain`, contains a text.
+#[macro_export]
+macro_rules! assert_error_contains {
+    ($result:expr, $text:expr $(,)?) => {{
+        let report = $crate::error::testing::expect_report($result);
+        let rendered = $crate::error::testing::render_plain(&report);
+        let text: &str = &$text;
+        assert!(
+            rendered.contains(text),
+            "expected the error to contain `{}`, but it is:\n{}",
+            text,
+            rendered
+        );
+    }};
+}
+
+/// Assert that a result is an error with a given message, or whose chain
+/// contains an error of a given type, optionally matching a pattern.
+///
+/// - `assert_fails_with!(result, "message")` compares the outermost message.
+/// - `assert_fails_with!(result, Type)` checks that the chain contains an
+///   error of type `Type`.
+/// - `assert_fails_with!(result, Type, pattern)` also checks that this error
+///   matches `pattern`, which can be followed by an `if` guard.
+#[macro_export]
+macro_rules! assert_fails_with {
+    ($result:expr, $message:literal $(,)?) => {{
+        let report = $crate::error::testing::expect_report($result);
+        assert_eq!(report.to_string(), $message, "unexpected error message");
+    }};
+    ($result:expr, $ty:ty $(,)?) => {{
+        use $crate::error::ReportExt as _;
+        let report = $crate::error::testing::expect_report($result);
+        assert!(
+            report.has_cause::<$ty>(),
+            "expected an error of type `{}`, but it is:\n{}",
+            stringify!($ty),
+            $crate::error::testing::render_plain(&report)
+        );
+    }};
+    ($result:expr, $ty:ty, $pattern:pat $(if $guard:expr)? $(,)?) => {{
+        use $crate::error::ReportExt as _;
+        let report = $crate::error::testing::expect_report($result);
+        assert!(
+            matches!(report.find_cause::<$ty>(), Some($pattern) $(if $guard)?),
+            "expected an error matching `{}`, but it is:\n{}",
+            stringify!($pattern),
+            $crate::error::testing::render_plain(&report)
+        );
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_sgr_sequences() {
+        let text = "\u{1b}[31mError:\u{1b}[0m \u{1b}[1;38;5;208mboom\u{1b}[39m!";
+        assert_eq!(strip_ansi(text), "Error: boom!");
+    }
+
+    #[test]
+    fn strip_other_escape_sequences() {
+        assert_eq!(strip_ansi("a\u{1b}[2Kb\u{1b}[?25lc"), "abc");
+        assert_eq!(strip_ansi("a\u{1b}7b"), "ab");
+        assert_eq!(strip_ansi("trailing\u{1b}["), "trailing");
+        assert_eq!(strip_ansi("no escape: [31m"), "no escape: [31m");
+    }
+
+    #[test]
+    fn mask_lines_and_columns() {
+        assert_eq!(
+            mask_line_numbers("at src/main.rs:12:5, src/lib.rs:7"),
+            "at src/main.rs:LL:CC, src/lib.rs:LL"
+        );
+        assert_eq!(
+            mask_line_numbers("src/main.rs:12: and src/main.rs:"),
+            "src/main.rs:LL: and src/main.rs:"
+        );
+        assert_eq!(
+            mask_line_numbers("file.rs:abc and main.rsx:12"),
+            "file.rs:abc and main.rsx:12"
+        );
+    }
+
+    #[test]
+    fn normalize_windows_paths() {
+        let normalizer = Normalizer::default()
+            .mask_line_numbers(true)
+            .base_dir("C:\\Users\\dev\\app\\");
+        let text = "Location:\r\n   C:\\Users\\dev\\app\\src\\main.rs:12:5\r\n";
+        assert_eq!(
+            normalizer.normalize(text),
+            "Location:\n   src/main.rs:LL:CC"
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_backslashes_of_messages() {
+        let normalizer = Normalizer::default().base_dir("/home/dev/app");
+        let text = "Error: invalid pattern \"\\d+\\.\\w\" in \"a\\tb\"\n\
+                    Location: src\\config.rs:3:1\n\
+                    at D:\\build\\lib.rs\n\
+                    at \\home\\dev\\app\\README";
+        assert_eq!(
+            normalizer.normalize(text),
+            "Error: invalid pattern \"\\d+\\.\\w\" in \"a\\tb\"\n\
+             Location: src/config.rs:3:1\n\
+             at D:/build/lib.rs\n\
+             at README"
+        );
+    }
+
+    #[test]
+    fn normalize_relativizes_the_longest_base_dir_first() {
+        let normalizer = Normalizer::default()
+            .base_dir("/home/dev")
+            .base_dir("/home/dev/app");
+        assert_eq!(
+            normalizer.normalize("at /home/dev/app/src/main.rs:3:1"),
+            "at src/main.rs:3:1"
+        );
+    }
+
+    #[test]
+    fn normalize_removes_env_hints_and_trailing_whitespaces() {
+        let text = "\n\u{1b}[31mError:\u{1b}[0m boom   \n\n\
+                    Backtrace omitted. Run with RUST_BACKTRACE=1 environment variable.\n\
+                    Run with RUST_BACKTRACE=full to include source snippets.\n";
+        assert_eq!(Normalizer::default().normalize(text), "Error: boom");
+    }
+
+    #[test]
+    fn render_plain_reports() {
+        install();
+        let report = crate::err!("Invalid port: {}", 80; help = "Use a port above 1024");
+        let rendered = Normalizer::new().mask_line_numbers(true).render(&report);
+        assert!(rendered.contains("Invalid port: 80"), "{rendered}");
+        assert!(
+            rendered.contains("Help: Use a port above 1024"),
+            "{rendered}"
+        );
+        assert!(!rendered.contains('\u{1b}'), "{rendered}");
+    }
+}
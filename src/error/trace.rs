@@ -0,0 +1,71 @@
+//! Emission of reports as tracing events.
+
+use super::{Report, ReportExt, Result, to_report};
+use tracing::Level;
+
+/// Trait to emit the errors of results as structured tracing events.
+///
+/// The events contain the message of the error, its chain of causes, and the
+/// location and code of the report, if any.
+///
+/// ```
+/// use extlib::error::{Result, TraceExt};
+/// use tracing::Level;
+///
+/// fn read_config() -> Result<String> {
+///     std::fs::read_to_string("config.toml").log_err()
+/// }
+///
+/// fn read_cache() -> Result<String> {
+///     std::fs::read_to_string(".cache").inspect_err_event(Level::WARN)
+/// }
+/// ```
+pub trait TraceExt<T> {
+    /// Emit an event of level `ERROR` if the result is an error.
+    fn log_err(self) -> Result<T>;
+
+    /// Emit an event of the given level if the result is an error.
+    fn inspect_err_event(self, level: Level) -> Result<T>;
+}
+
+impl<T, E> TraceExt<T> for Result<T, E>
+where
+    E: Into<Report>,
+{
+    #[track_caller]
+    fn log_err(self) -> Result<T> {
+        self.inspect_err_event(Level::ERROR)
+    }
+
+    #[track_caller]
+    fn inspect_err_event(self, level: Level) -> Result<T> {
+        let report = match self {
+            Ok(value) => return Ok(value),
+            Err(err) => to_report(err),
+        };
+        emit_event(&report, level);
+        Err(report)
+    }
+}
+
+/// Emit a tracing event describing a report.
+fn emit_event(report: &Report, level: Level) {
+    macro_rules! emit {
+        ($level:expr) => {
+            tracing::event!(
+                $level,
+                error.chain = %format!("{report:#}"),
+                error.location = report.location().map(tracing::field::display),
+                error.code = report.error_code().map(|code| tracing::field::display(code.code())),
+                "{report}"
+            )
+        };
+    }
+    match level {
+        Level::ERROR => emit!(Level::ERROR),
+        Level::WARN => emit!(Level::WARN),
+        Level::INFO => emit!(Level::INFO),
+        Level::DEBUG => emit!(Level::DEBUG),
+        _ => emit!(Level::TRACE),
+    }
+}
@@ -1,3 +1,7 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 /// Trait to extend String utilities.
 pub trait StringExt<'a> {
     /// Indent all lines of a string.
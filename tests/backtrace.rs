@@ -0,0 +1,24 @@
+//! Backtraces recorded without calling `ErrorConfig::install`.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorRecord, Result};
+use extlib::fail;
+
+fn fetch() -> Result<()> {
+    fail!("Cannot fetch")
+}
+
+#[test]
+fn backtraces_are_recorded_without_install() {
+    // SAFETY: no other thread reads the environment in this test binary.
+    unsafe { std::env::set_var("RUST_LIB_BACKTRACE", "1") };
+    let record = ErrorRecord::new(&fetch().unwrap_err());
+    assert!(
+        record.backtrace.iter().any(|frame| frame
+            .name
+            .as_deref()
+            .is_some_and(|name| name.contains("fetch"))),
+        "{:#?}",
+        record.backtrace
+    );
+}
@@ -0,0 +1,50 @@
+//! Error codes attached to reports without calling `ErrorConfig::install`.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorCategory, ErrorCode, ReportExt, Result, ResultExt};
+use extlib::fail;
+use std::fmt;
+
+#[derive(Debug)]
+struct MissingInput;
+
+impl fmt::Display for MissingInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing input")
+    }
+}
+
+impl ErrorCode for MissingInput {
+    fn code(&self) -> &str {
+        "E0001"
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::NotFound
+    }
+}
+
+fn open() -> Result<()> {
+    fail!(code = MissingInput, "boom")
+}
+
+#[test]
+fn code_is_recorded_without_install() {
+    let report = open().unwrap_err();
+    assert_eq!(report.to_string(), "boom");
+    assert_eq!(report.error_code().map(|code| code.code()), Some("E0001"));
+    assert_eq!(report.exit_status(), 66);
+}
+
+#[test]
+fn code_is_kept_through_contexts() {
+    let report = open().context("Cannot start").unwrap_err();
+    assert_eq!(report.exit_status(), 66);
+}
+
+#[test]
+fn code_can_be_attached_to_converted_reports() {
+    let report = "x".parse::<u32>().context_here().unwrap_err();
+    let report = report.with_code(MissingInput);
+    assert_eq!(report.error_code().map(|code| code.code()), Some("E0001"));
+}
@@ -0,0 +1,141 @@
+//! Errors derived by `ExtError`.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorCategory, ErrorCode, LocationPolicy, ReportExt, Result};
+use extlib::fail;
+use extlib_derive::ExtError;
+use std::error::Error;
+
+#[derive(Debug, ExtError)]
+#[error(category = InvalidData)]
+enum ConfigError {
+    #[error("file not found: {path}", code = "C001", category = NotFound)]
+    NotFound { path: String },
+    #[error("invalid value of {0}", code = "C002")]
+    Invalid(String),
+    #[error("cannot parse the config", code = "C003", exit_status = 2)]
+    Parse(#[source] std::num::ParseIntError),
+}
+
+#[derive(Debug, ExtError)]
+#[error("bad {f}")]
+struct Shadowing {
+    f: String,
+}
+
+#[derive(Debug, ExtError)]
+#[error("cannot load {0}: {1}")]
+struct Tuple(String, u32);
+
+#[derive(Debug, ExtError)]
+#[error("bad value {0:?} (limit {})", self.limit())]
+struct Named {
+    value: u32,
+}
+
+impl Named {
+    fn limit(&self) -> u32 {
+        self.value / 2
+    }
+}
+
+#[derive(Debug, ExtError)]
+#[error("{0} out of {1} (expected {2:?})", self.0 * 2)]
+struct Positional(u32, u32);
+
+#[derive(Debug, ExtError)]
+#[error("request failed")]
+struct Boxed {
+    source: Box<dyn Error + Send + Sync>,
+}
+
+#[derive(Debug, ExtError)]
+#[error("unit error", code = "U001", category = Unavailable)]
+struct Unit;
+
+#[test]
+fn enum_variants_are_displayed() {
+    let err = ConfigError::NotFound {
+        path: "app.toml".to_string(),
+    };
+    assert_eq!(err.to_string(), "file not found: app.toml");
+    let err = ConfigError::Invalid("port".to_string());
+    assert_eq!(err.to_string(), "invalid value of port");
+}
+
+#[test]
+fn struct_fields_are_displayed() {
+    let err = Shadowing {
+        f: "input".to_string(),
+    };
+    assert_eq!(err.to_string(), "bad input");
+    assert_eq!(
+        Tuple("app.toml".to_string(), 3).to_string(),
+        "cannot load app.toml: 3"
+    );
+    assert_eq!(Unit.to_string(), "unit error");
+}
+
+#[test]
+fn explicit_positional_arguments_are_displayed() {
+    assert_eq!(Named { value: 10 }.to_string(), "bad value 5 (limit 5)");
+    assert_eq!(Positional(3, 4).to_string(), "3 out of 4 (expected 6)");
+}
+
+#[test]
+fn codes_categories_and_exit_statuses_are_derived() {
+    let err = ConfigError::NotFound {
+        path: "app.toml".to_string(),
+    };
+    assert_eq!(err.code(), "C001");
+    assert_eq!(err.category(), ErrorCategory::NotFound);
+    assert_eq!(err.exit_status(), 66);
+
+    let err = ConfigError::Invalid("port".to_string());
+    assert_eq!(err.code(), "C002");
+    assert_eq!(err.category(), ErrorCategory::InvalidData);
+    assert_eq!(err.exit_status(), 65);
+
+    let err = ConfigError::Parse("x".parse::<u32>().unwrap_err());
+    assert_eq!(err.code(), "C003");
+    assert_eq!(err.exit_status(), 2);
+
+    assert_eq!(Unit.code(), "U001");
+    assert_eq!(Unit.category(), ErrorCategory::Unavailable);
+}
+
+#[test]
+fn sources_are_returned() {
+    let err = ConfigError::Parse("x".parse::<u32>().unwrap_err());
+    let source = err.source().expect("source should be returned");
+    assert!(source.is::<std::num::ParseIntError>());
+    assert!(ConfigError::Invalid("port".to_string()).source().is_none());
+
+    let err = Boxed {
+        source: "x".parse::<u32>().unwrap_err().into(),
+    };
+    let source = err.source().expect("boxed source should be returned");
+    assert!(source.is::<std::num::ParseIntError>());
+}
+
+fn load(path: &str) -> Result<()> {
+    fail!(ConfigError::NotFound {
+        path: path.to_string()
+    })
+}
+
+#[test]
+fn failed_errors_can_be_downcast() {
+    let report = load("app.toml").unwrap_err();
+    assert!(matches!(
+        report.downcast_ref::<ConfigError>(),
+        Some(ConfigError::NotFound { path }) if path == "app.toml"
+    ));
+    assert_eq!(report.error_code().map(|code| code.code()), Some("C001"));
+    assert_eq!(report.exit_status(), 66);
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(
+        report.location().map(|location| location.file()),
+        captures.then_some(file!())
+    );
+}
@@ -0,0 +1,63 @@
+//! Reports of multiple collected errors.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorCategory, ErrorCode, Errors, ReportExt};
+use extlib::{err, push_error};
+use std::fmt;
+
+#[derive(Debug)]
+struct Unavailable;
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "service unavailable")
+    }
+}
+
+impl ErrorCode for Unavailable {
+    fn code(&self) -> &str {
+        "S001"
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::Unavailable
+    }
+}
+
+#[test]
+fn sections_and_codes_of_errors_are_rendered() {
+    let mut errors = Errors::new();
+    errors.push(err!("a"; help = "fix a"));
+    push_error!(errors, code = Unavailable, "b"; note = "retry later");
+    let report = errors.finish().unwrap_err();
+    let rendered = report.to_string();
+    assert!(
+        rendered.starts_with("2 errors occurred:\n   1. a"),
+        "{rendered}"
+    );
+    assert!(rendered.contains("\n      Help: fix a"), "{rendered}");
+    assert!(
+        rendered.contains("\n      Code: S001 (unavailable)"),
+        "{rendered}"
+    );
+    assert!(rendered.contains("\n      Note: retry later"), "{rendered}");
+}
+
+#[test]
+fn exit_status_is_derived_from_the_first_coded_error() {
+    let mut errors = Errors::new();
+    push_error!(errors, "a");
+    push_error!(errors, code = Unavailable, "b");
+    let report = errors.finish().unwrap_err();
+    assert!(report.downcast_ref::<Errors>().is_some());
+    assert_eq!(report.error_code().map(|code| code.code()), Some("S001"));
+    assert_eq!(report.exit_status(), 69);
+}
+
+#[test]
+fn exit_status_defaults_to_one_without_codes() {
+    let mut errors = Errors::new();
+    push_error!(errors, "a");
+    push_error!(errors, "b");
+    assert_eq!(errors.finish().unwrap_err().exit_status(), 1);
+}
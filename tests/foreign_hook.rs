@@ -0,0 +1,65 @@
+//! Metadata of reports recorded when another `eyre` hook is set first.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorRecord, LocationPolicy, Report, ReportExt, Result, ResultExt};
+use extlib::fail;
+
+fn parse(text: &str) -> Result<u32> {
+    match text.parse() {
+        Ok(value) => Ok(value),
+        Err(_) => fail!("Invalid number: {text}"),
+    }
+}
+
+/// Create a report before any report of this crate, which sets the default
+/// hook of `eyre`.
+fn set_foreign_hook() {
+    let _ = Report::msg("foreign");
+}
+
+#[test]
+fn location_is_recorded_with_foreign_hook() {
+    set_foreign_hook();
+    let line = line!() - 13;
+    let report = parse("x").unwrap_err();
+    assert_eq!(report.to_string(), "Invalid number: x");
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(
+        report
+            .location()
+            .map(|location| (location.file(), location.line())),
+        captures.then_some((file!(), line))
+    );
+}
+
+#[test]
+fn typed_errors_can_be_downcast_with_foreign_hook() {
+    set_foreign_hook();
+    let report = "x".parse::<u32>().context_here().unwrap_err();
+    assert!(report.has_cause::<std::num::ParseIntError>());
+    assert!(report.root_cause_as::<std::num::ParseIntError>().is_some());
+    let captures = LocationPolicy::current().captures();
+    let files: Vec<&str> = report
+        .contexts()
+        .iter()
+        .map(|frame| frame.location().file())
+        .collect();
+    assert_eq!(files, captures.then_some(file!()).as_slice());
+}
+
+#[test]
+fn chain_is_unchanged_with_foreign_hook() {
+    set_foreign_hook();
+    let report = "x"
+        .parse::<u32>()
+        .context_here()
+        .context("Cannot parse")
+        .unwrap_err();
+    let chain: Vec<String> = report.chain().map(|err| err.to_string()).collect();
+    assert_eq!(chain, ["Cannot parse", "invalid digit found in string"]);
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(report.contexts().len(), if captures { 2 } else { 0 });
+
+    let record = ErrorRecord::new(&report);
+    assert_eq!(record.causes, ["invalid digit found in string"]);
+}
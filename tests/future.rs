@@ -0,0 +1,189 @@
+//! Contexts of futures and streams polled without an async runtime.
+#![cfg(feature = "async")]
+
+use extlib::error::{FutureExt, LocationPolicy, Report, ReportExt, Result, StreamExt};
+use extlib::fail;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::num::ParseIntError;
+use std::pin::{Pin, pin};
+use std::task::{Context, Poll, Waker};
+
+/// Future returning `Pending` a number of times before its output.
+struct Delayed<T> {
+    pending: usize,
+    output: Option<T>,
+}
+
+impl<T> Delayed<T> {
+    fn new(pending: usize, output: T) -> Self {
+        Delayed {
+            pending,
+            output: Some(output),
+        }
+    }
+}
+
+impl<T: Unpin> Future for Delayed<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+        if self.pending > 0 {
+            self.pending -= 1;
+            return Poll::Pending;
+        }
+        Poll::Ready(self.output.take().expect("polled after completion"))
+    }
+}
+
+/// Stream yielding items, with a `Pending` before each of them.
+struct Items<T> {
+    items: VecDeque<T>,
+    ready: bool,
+}
+
+impl<T: Unpin> Stream for Items<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.ready = !self.ready;
+        if self.ready {
+            Poll::Ready(self.items.pop_front())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.items.len(), Some(self.items.len()))
+    }
+}
+
+/// Poll a future until it is ready, and return the number of polls.
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    for polls in 1.. {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return (output, polls);
+        }
+    }
+    unreachable!()
+}
+
+/// Poll a stream until it is exhausted, and return its items.
+fn collect<S: Stream>(stream: S) -> Vec<S::Item> {
+    let mut stream = pin!(stream);
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut items = vec![];
+    loop {
+        match stream.as_mut().poll_next(&mut cx) {
+            Poll::Pending => continue,
+            Poll::Ready(Some(item)) => items.push(item),
+            Poll::Ready(None) => return items,
+        }
+    }
+}
+
+fn chain(report: &Report) -> Vec<String> {
+    report.chain().map(|err| err.to_string()).collect()
+}
+
+fn parse_error() -> ParseIntError {
+    "x".parse::<u32>().unwrap_err()
+}
+
+#[test]
+fn futures_attach_their_message_once() {
+    let future = Delayed::new(3, Err::<u32, _>(parse_error())).in_context("Cannot load");
+    let location = future.location().clone();
+    assert_eq!(location.file(), file!());
+
+    let (result, polls) = block_on(future);
+    assert_eq!(polls, 4);
+    let report = result.unwrap_err();
+    assert_eq!(
+        chain(&report),
+        ["Cannot load", "invalid digit found in string"]
+    );
+    assert!(report.has_cause::<ParseIntError>());
+    // The report is created by the adaptor, so that it is located where the
+    // adaptor was created, without a context frame at the same location.
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(report.location(), captures.then_some(&location));
+    assert!(report.contexts().is_empty());
+}
+
+#[test]
+fn futures_add_context_frames_to_located_reports() {
+    let future = Delayed::new(1, fail_here()).context_here();
+    let location = future.location().clone();
+
+    let (result, _) = block_on(future);
+    let report = result.unwrap_err();
+    assert_eq!(chain(&report), ["Cannot connect"]);
+    let captures = LocationPolicy::current().captures();
+    let contexts: Vec<_> = report
+        .contexts()
+        .iter()
+        .map(|frame| (frame.message(), frame.location().clone()))
+        .collect();
+    if captures {
+        assert_ne!(report.location(), Some(&location));
+        assert_eq!(contexts, [(None, location)]);
+    } else {
+        assert!(contexts.is_empty());
+    }
+}
+
+fn fail_here() -> Result<u32> {
+    fail!("Cannot connect")
+}
+
+#[test]
+fn futures_return_values() {
+    let (result, polls) = block_on(Delayed::new(0, Ok::<_, ParseIntError>(3)).in_context("unused"));
+    assert_eq!(result.unwrap(), 3);
+    assert_eq!(polls, 1);
+}
+
+#[test]
+fn streams_attach_their_message_to_every_error() {
+    let items = VecDeque::from([Ok(1), Err(parse_error()), Ok(2), Err(parse_error())]);
+    let stream = Items {
+        items,
+        ready: false,
+    }
+    .in_context("Invalid record");
+    assert_eq!(stream.size_hint(), (4, Some(4)));
+    let location = stream.location().clone();
+
+    let items = collect(stream);
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0].as_ref().unwrap(), &1);
+    assert_eq!(items[2].as_ref().unwrap(), &2);
+    let captures = LocationPolicy::current().captures();
+    for item in [&items[1], &items[3]] {
+        let report = item.as_ref().unwrap_err();
+        assert_eq!(
+            chain(report),
+            ["Invalid record", "invalid digit found in string"]
+        );
+        assert_eq!(report.location(), captures.then_some(&location));
+    }
+}
+
+#[test]
+fn streams_without_message_keep_the_chain() {
+    let items = VecDeque::from([Err::<u32, _>(parse_error())]);
+    let stream = Items {
+        items,
+        ready: false,
+    }
+    .context_here();
+
+    let items = collect(stream);
+    let report = items[0].as_ref().unwrap_err();
+    assert_eq!(chain(report), ["invalid digit found in string"]);
+}
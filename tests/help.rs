@@ -0,0 +1,41 @@
+//! Help sections attached to reports without calling `ErrorConfig::install`.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorExt, HelpKind, ReportExt, Result, ResultExt};
+use extlib::fail;
+
+fn open() -> Result<()> {
+    fail!("Cannot open the file"; note = "It is missing", help = "Create it")
+}
+
+#[test]
+fn sections_are_recorded_without_install() {
+    let report = open().unwrap_err();
+    let sections: Vec<_> = report
+        .help_sections()
+        .iter()
+        .map(|section| (section.kind(), section.message()))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            (HelpKind::Note, "It is missing"),
+            (HelpKind::Help, "Create it")
+        ]
+    );
+}
+
+#[test]
+fn sections_are_rendered_without_install() {
+    let report = open().unwrap_err();
+    let rendered = format!("{report:?}");
+    assert!(rendered.contains("Note: It is missing"), "{rendered}");
+    assert!(rendered.contains("Help: Create it"), "{rendered}");
+}
+
+#[test]
+fn sections_can_be_attached_to_converted_reports() {
+    let report = "x".parse::<u32>().context_here().unwrap_err();
+    let report = report.with_warning("Check the input");
+    assert_eq!(report.help_sections()[0].message(), "Check the input");
+}
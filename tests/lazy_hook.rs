@@ -0,0 +1,58 @@
+//! Metadata of reports recorded without calling `ErrorConfig::install`.
+#![cfg(feature = "std")]
+
+use extlib::error::{LocationPolicy, ReportExt, Result, ResultExt};
+use extlib::fail;
+
+fn parse(text: &str) -> Result<u32> {
+    match text.parse() {
+        Ok(value) => Ok(value),
+        Err(_) => fail!("Invalid number: {text}"),
+    }
+}
+
+#[test]
+fn location_is_recorded_without_install() {
+    let line = line!() - 6;
+    let report = parse("x").unwrap_err();
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(
+        report
+            .location()
+            .map(|location| (location.file(), location.line())),
+        captures.then_some((file!(), line))
+    );
+}
+
+#[test]
+fn contexts_are_recorded_without_install() {
+    let report = parse("x").context("Cannot read the config").unwrap_err();
+    assert_eq!(report.to_string(), "Cannot read the config");
+    let captures = LocationPolicy::current().captures();
+    let files: Vec<&str> = report
+        .contexts()
+        .iter()
+        .map(|frame| frame.location().file())
+        .collect();
+    assert_eq!(files, captures.then_some(file!()).as_slice());
+}
+
+#[test]
+fn location_is_rendered_without_install() {
+    let report = parse("x").unwrap_err();
+    let displays = LocationPolicy::current().displays();
+    assert_eq!(
+        format!("{report:?}").contains(&format!("Location:\n   {}", file!())),
+        displays
+    );
+}
+
+#[test]
+fn report_error_is_located_at_the_caller() {
+    let report = extlib::error::report_error::<()>("boom").unwrap_err();
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(
+        report.location().map(|location| location.file()),
+        captures.then_some(file!())
+    );
+}
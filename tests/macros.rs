@@ -0,0 +1,173 @@
+//! Macros raising errors with a source error as their cause.
+#![cfg(feature = "std")]
+
+use extlib::error::{ErrorCategory, ErrorCode, HelpKind, LocationPolicy, ReportExt, Result};
+use extlib::{bail_if, ensure_eq, ensure_matches, ensure_ne, err_with};
+use std::num::ParseIntError;
+
+fn parse(text: &str, strict: bool) -> Result<u32> {
+    let parsed = text.parse::<u32>();
+    if let Err(err) = &parsed {
+        bail_if!(strict, source = err.clone(), "Invalid number: {text}");
+    }
+    Ok(parsed.unwrap_or_default())
+}
+
+fn parse_source_only(text: &str) -> Result<u32> {
+    let parsed = text.parse::<u32>();
+    if let Err(err) = &parsed {
+        bail_if!(true, source = err.clone());
+    }
+    Ok(parsed.unwrap_or_default())
+}
+
+#[test]
+fn bail_if_keeps_the_source_as_cause() {
+    let report = parse("x", true).unwrap_err();
+    assert_eq!(report.to_string(), "Invalid number: x");
+    assert!(report.has_cause::<ParseIntError>());
+    assert_eq!(report.chain().count(), 2);
+    assert_eq!(parse("x", false).unwrap(), 0);
+}
+
+#[test]
+fn bail_if_with_only_a_source_raises_it() {
+    let report = parse_source_only("x").unwrap_err();
+    assert!(report.downcast_ref::<ParseIntError>().is_some());
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(
+        report.location().map(|location| location.file()),
+        captures.then_some(file!())
+    );
+}
+
+#[test]
+fn err_with_keeps_the_source_as_cause() {
+    let report = "x"
+        .parse::<u32>()
+        .map_err(|err| err_with!(err, "Invalid port: {}", "x"))
+        .unwrap_err();
+    assert_eq!(report.to_string(), "Invalid port: x");
+    assert!(report.has_cause::<ParseIntError>());
+}
+
+#[derive(Debug)]
+struct InvalidHeader;
+
+impl std::fmt::Display for InvalidHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid header")
+    }
+}
+
+impl ErrorCode for InvalidHeader {
+    fn code(&self) -> &str {
+        "E0002"
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::InvalidData
+    }
+}
+
+fn check_version(version: u32) -> Result<()> {
+    ensure_eq!(version, 2);
+    Ok(())
+}
+
+fn check_header(magic: &str) -> Result<()> {
+    ensure_eq!(
+        magic,
+        "EXT1",
+        code = InvalidHeader,
+        "Invalid header {magic:?}";
+        help = "Is it an EXT file?"
+    );
+    Ok(())
+}
+
+fn check_port(text: &str) -> Result<()> {
+    let parsed = text.parse::<u16>();
+    ensure_matches!(
+        parsed,
+        Ok(port) if port > 0,
+        source = parsed.clone().unwrap_err(),
+        "Invalid port {text}"
+    );
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<()> {
+    ensure_ne!(name, "", "Empty name"; note = "names are required");
+    Ok(())
+}
+
+#[test]
+fn ensure_eq_reports_the_operands() {
+    assert!(check_version(2).is_ok());
+    let report = check_version(3).unwrap_err();
+    assert_eq!(
+        report.to_string(),
+        "assertion `version == 2` failed\n  left: 3\n right: 2"
+    );
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(
+        report.location().map(|location| location.file()),
+        captures.then_some(file!())
+    );
+}
+
+#[test]
+fn ensure_eq_accepts_codes_and_sections() {
+    assert!(check_header("EXT1").is_ok());
+    let report = check_header("ZIP").unwrap_err();
+    assert_eq!(report.to_string(), "Invalid header \"ZIP\"");
+    assert_eq!(report.error_code().map(|code| code.code()), Some("E0002"));
+    let sections: Vec<(HelpKind, &str)> = report
+        .help_sections()
+        .iter()
+        .map(|section| (section.kind(), section.message()))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            (HelpKind::Help, "Is it an EXT file?"),
+            (
+                HelpKind::Note,
+                "assertion `magic == \"EXT1\"` failed\n  left: \"ZIP\"\n right: \"EXT1\""
+            ),
+        ]
+    );
+}
+
+#[test]
+fn ensure_ne_accepts_sections() {
+    assert!(check_name("app").is_ok());
+    let report = check_name("").unwrap_err();
+    assert_eq!(report.to_string(), "Empty name");
+    let notes: Vec<&str> = report
+        .help_sections()
+        .iter()
+        .map(|section| section.message())
+        .collect();
+    assert_eq!(
+        notes,
+        [
+            "names are required",
+            "assertion `name != \"\"` failed\n  left: \"\"\n right: \"\""
+        ]
+    );
+}
+
+#[test]
+fn ensure_matches_keeps_the_source_as_cause() {
+    assert!(check_port("8080").is_ok());
+    let report = check_port("x").unwrap_err();
+    assert_eq!(report.to_string(), "Invalid port x");
+    assert!(report.has_cause::<ParseIntError>());
+    assert_eq!(
+        report.help_sections()[0].message(),
+        "assertion `parsed matches Ok(port) if port > 0` failed\n  \
+         left: Err(ParseIntError { kind: InvalidDigit })"
+    );
+}
@@ -0,0 +1,67 @@
+//! Panics converted to reports, and reports converted to panics.
+#![cfg(feature = "std")]
+
+use extlib::err;
+use extlib::error::{LocationPolicy, Panic, ReportExt, catch_panic, panic_on_error, testing};
+
+#[test]
+fn values_are_returned() {
+    assert_eq!(catch_panic(|| 3).unwrap(), 3);
+}
+
+#[test]
+fn panics_are_converted_to_reports() {
+    testing::install();
+    let line = line!() + 1;
+    let report = catch_panic(|| panic!("boom {}", 3)).unwrap_err();
+    assert_eq!(report.to_string(), "Panicked: boom 3");
+    let panic = report
+        .downcast_ref::<Panic>()
+        .expect("report should be a panic");
+    assert_eq!(panic.message(), "boom 3");
+    let location = panic
+        .location()
+        .expect("location should be recorded by the hook");
+    assert_eq!((location.file(), location.line()), (file!(), line));
+    let captures = LocationPolicy::current().captures();
+    assert_eq!(report.location(), captures.then_some(location));
+}
+
+#[test]
+fn panic_payloads_are_converted_to_messages() {
+    let report = catch_panic(|| panic!("static message")).unwrap_err();
+    assert_eq!(report.to_string(), "Panicked: static message");
+    let report = catch_panic(|| std::panic::panic_any(42)).unwrap_err();
+    assert_eq!(report.to_string(), "Panicked: Box<dyn Any>");
+}
+
+#[test]
+fn nested_panics_are_caught_by_the_innermost_call() {
+    let result = catch_panic(|| {
+        let inner = catch_panic(|| panic!("inner")).unwrap_err();
+        inner.to_string()
+    });
+    assert_eq!(result.unwrap(), "Panicked: inner");
+}
+
+#[test]
+fn values_of_results_are_returned() {
+    assert_eq!(panic_on_error(Ok(3)), 3);
+}
+
+#[test]
+#[should_panic(expected = "Invalid port: 80")]
+fn errors_of_results_panic_with_their_report() {
+    panic_on_error::<()>(Err(err!("Invalid port: {}", 80)));
+}
+
+#[test]
+fn panics_of_errors_can_be_caught() {
+    let report = catch_panic(|| panic_on_error::<()>(Err(err!("Invalid port")))).unwrap_err();
+    let panic = report.downcast_ref::<Panic>().unwrap();
+    assert!(
+        panic.message().contains("Invalid port"),
+        "{}",
+        panic.message()
+    );
+}
@@ -0,0 +1,59 @@
+//! Records of reports serialized to JSON and replayed.
+#![cfg(feature = "serde")]
+
+use extlib::error::{ErrorCategory, ErrorCode, ErrorRecord, Result, ResultExt};
+use extlib::fail;
+use std::fmt;
+
+#[derive(Debug)]
+struct Unreachable;
+
+impl fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unreachable")
+    }
+}
+
+impl ErrorCode for Unreachable {
+    fn code(&self) -> &str {
+        "N0001"
+    }
+
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::Unavailable
+    }
+}
+
+fn connect() -> Result<()> {
+    fail!(code = Unreachable, "Connection refused"; help = "Start the server")
+}
+
+fn sync() -> Result<()> {
+    connect().context("Cannot sync")
+}
+
+#[test]
+fn records_are_replayed_from_json() {
+    let mut record = ErrorRecord::new(&sync().unwrap_err());
+    record.backtrace.clear();
+    assert_eq!(record.causes, ["Connection refused"]);
+    assert_eq!(
+        record.code.as_ref().map(|code| code.code.as_str()),
+        Some("N0001")
+    );
+
+    let json = record.to_json().unwrap();
+    let deserialized = ErrorRecord::from_json(&json).unwrap();
+    assert_eq!(deserialized, record);
+
+    let report = deserialized.into_report();
+    assert_eq!(report.to_string(), "Cannot sync");
+    let mut replayed = ErrorRecord::new(&report);
+    replayed.backtrace.clear();
+    assert_eq!(replayed, record);
+}
+
+#[test]
+fn invalid_json_is_rejected() {
+    assert!(ErrorRecord::from_json("{\"message\": 1}").is_err());
+}
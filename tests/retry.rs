@@ -0,0 +1,51 @@
+//! Delays of retry backoffs.
+#![cfg(feature = "std")]
+
+use extlib::error::Backoff;
+use std::time::Duration;
+
+#[test]
+fn exponential_delays_are_capped() {
+    let backoff = Backoff::Exponential {
+        initial: Duration::from_millis(100),
+        factor: 2.0,
+        max: Duration::from_millis(300),
+    };
+    assert_eq!(backoff.delay(1), Duration::from_millis(100));
+    assert_eq!(backoff.delay(2), Duration::from_millis(200));
+    assert_eq!(backoff.delay(3), Duration::from_millis(300));
+    assert_eq!(backoff.delay(u32::MAX), Duration::from_millis(300));
+}
+
+#[test]
+fn factors_below_one_keep_the_initial_delay() {
+    for factor in [-2.0, 0.0, 0.5] {
+        let backoff = Backoff::Exponential {
+            initial: Duration::from_millis(100),
+            factor,
+            max: Duration::from_secs(1),
+        };
+        for attempt in 1..5 {
+            assert_eq!(backoff.delay(attempt), Duration::from_millis(100));
+        }
+        let backoff = Backoff::Jittered {
+            initial: Duration::from_millis(100),
+            factor,
+            max: Duration::from_secs(1),
+        };
+        assert!(backoff.delay(3) <= Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn non_finite_factors_keep_the_initial_delay() {
+    for factor in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let backoff = Backoff::Exponential {
+            initial: Duration::from_millis(100),
+            factor,
+            max: Duration::from_secs(1),
+        };
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(3), Duration::from_millis(100));
+    }
+}
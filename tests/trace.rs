@@ -0,0 +1,135 @@
+//! Span traces and tracing events of reports.
+#![cfg(feature = "tracing")]
+
+use extlib::error::{ErrorCategory, ErrorCodeRecord, ReportExt, Result, ResultExt, TraceExt};
+use extlib::fail;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_error::ErrorLayer;
+use tracing_subscriber::Registry;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+/// Fields of a recorded event.
+type Fields = BTreeMap<&'static str, String>;
+
+/// Layer recording the level and fields of events.
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<(Level, Fields)>>>,
+}
+
+impl Recorder {
+    fn events(&self) -> Vec<(Level, Fields)> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Recorder {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        event.record(&mut FieldVisitor(&mut fields));
+        let level = *event.metadata().level();
+        self.events.lock().unwrap().push((level, fields));
+    }
+}
+
+/// Run a closure with a subscriber recording events and span traces.
+fn with_recorder<T>(f: impl FnOnce() -> T) -> (T, Vec<(Level, Fields)>) {
+    let recorder = Recorder::default();
+    let subscriber = Registry::default()
+        .with(ErrorLayer::default())
+        .with(recorder.clone());
+    let value = tracing::subscriber::with_default(subscriber, f);
+    (value, recorder.events())
+}
+
+fn load(path: &str) -> Result<()> {
+    fail!("Cannot load {path}")
+}
+
+#[test]
+fn span_traces_are_captured_without_install() {
+    let (report, _) = with_recorder(|| {
+        let span = tracing::info_span!("load_config", path = "app.toml");
+        span.in_scope(|| load("app.toml").unwrap_err())
+    });
+    let span_trace = report.span_trace().expect("span trace should be captured");
+    assert!(
+        span_trace.to_string().contains("load_config"),
+        "{span_trace}"
+    );
+}
+
+#[test]
+fn span_traces_are_not_captured_without_error_layer() {
+    let report = load("app.toml").unwrap_err();
+    assert!(report.span_trace().is_none());
+}
+
+#[test]
+fn log_err_emits_error_events() {
+    let (result, events) = with_recorder(|| load("app.toml").log_err());
+    let report = result.unwrap_err();
+    assert_eq!(events.len(), 1);
+    let (level, fields) = &events[0];
+    assert_eq!(*level, Level::ERROR);
+    assert_eq!(fields["message"], "Cannot load app.toml");
+    assert_eq!(fields["error.chain"], "Cannot load app.toml");
+    match report.location() {
+        Some(location) => assert_eq!(fields["error.location"], location.to_string()),
+        None => assert!(!fields.contains_key("error.location")),
+    }
+}
+
+#[test]
+fn inspect_err_event_emits_events_of_the_given_level() {
+    let (result, events) = with_recorder(|| {
+        "x".parse::<u32>()
+            .inspect_err_event(Level::WARN)
+            .map_err(|report| report.wrap_err("Invalid port"))
+    });
+    assert!(result.is_err());
+    assert_eq!(events.len(), 1);
+    let (level, fields) = &events[0];
+    assert_eq!(*level, Level::WARN);
+    assert_eq!(fields["message"], "invalid digit found in string");
+    assert!(!fields.contains_key("error.code"));
+}
+
+#[test]
+fn events_contain_error_codes() {
+    let code = ErrorCodeRecord {
+        code: "C001".to_string(),
+        category: ErrorCategory::NotFound,
+        exit_status: 66,
+        http_status: 404,
+    };
+    let (_, events) = with_recorder(|| {
+        load("app.toml")
+            .map_err(|report| report.with_code(code))
+            .context("Cannot start")
+            .log_err()
+    });
+    let (_, fields) = &events[0];
+    assert_eq!(fields["message"], "Cannot start");
+    assert_eq!(fields["error.chain"], "Cannot start: Cannot load app.toml");
+    assert_eq!(fields["error.code"], "C001");
+}
+
+#[test]
+fn successful_results_emit_no_events() {
+    let (result, events) = with_recorder(|| Ok::<_, std::io::Error>(3).log_err());
+    assert_eq!(result.unwrap(), 3);
+    assert!(events.is_empty());
+}